@@ -0,0 +1,250 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use similar::DiffOp;
+use similar::TextDiff;
+use std::collections::HashMap;
+
+use crate::commons;
+use crate::diff_command;
+use crate::error;
+use crate::repository::factory;
+use crate::Command;
+use crate::Repository;
+use crate::Revision;
+use crate::ZatsuError;
+
+pub struct BlameCommand {
+    path: String,
+    revision_number: Option<i32>,
+}
+
+/// A line of a file with the oldest revision in which it appeared unchanged.
+pub struct BlameLine {
+    pub revision_number: i32,
+    pub text: String,
+}
+
+impl Command for BlameCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+
+        let mut commited_times: HashMap<i32, i64> = HashMap::new();
+        let lines = self.lines(&repository, &mut commited_times)?;
+        let revision_width = repository.latest_revision().to_string().len();
+        let line_width = lines.len().to_string().len();
+        for (i, line) in lines.iter().enumerate() {
+            println!(
+                "{:>revision_width$} {} {:>line_width$}| {}",
+                line.revision_number,
+                commons::formatted_time(commited_times[&line.revision_number]),
+                i + 1,
+                line.text
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl BlameCommand {
+    pub fn new(path: &str, revision_number: Option<i32>) -> Self {
+        Self {
+            path: path.to_string(),
+            revision_number,
+        }
+    }
+
+    /// Returns lines of the file, walking revisions backward. Commited times of
+    /// revisions that lines belong to are stored into commited_times.
+    pub fn lines(
+        &self,
+        repository: &Box<dyn Repository>,
+        commited_times: &mut HashMap<i32, i64>,
+    ) -> Result<Vec<BlameLine>, ZatsuError> {
+        let revision_numbers = repository.revision_numbers();
+        let revision_number = self.revision_number.unwrap_or(repository.latest_revision());
+        let revision = commons::load_revision(repository, revision_number)?;
+        let path = self.path.strip_prefix("./").unwrap_or(&self.path);
+        let entry = match revision.entries.iter().find(|entry| entry.path == path) {
+            Some(entry) => entry,
+            None => {
+                println!(
+                    "Error: {} not found in revision {}.",
+                    self.path, revision_number
+                );
+                return Err(ZatsuError::new(error::CODE_FILE_NOT_FOUND));
+            }
+        };
+        let mut hash = entry.hash.clone();
+        let mut text = match load_text(&hash)? {
+            Some(text) => text,
+            None => {
+                println!("Error: {} is a binary file.", self.path);
+                return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED));
+            }
+        };
+        commited_times.insert(revision_number, revision.commited);
+
+        let mut lines: Vec<BlameLine> = text
+            .lines()
+            .map(|line| BlameLine {
+                revision_number,
+                text: line.to_string(),
+            })
+            .collect();
+        // Pairs of indices of lines in the result and in the version being compared.
+        let mut tracked: Vec<(usize, usize)> = (0..lines.len()).map(|i| (i, i)).collect();
+
+        let position = revision_numbers
+            .iter()
+            .position(|number| *number == revision_number)
+            .unwrap_or(0);
+        for i in (0..position).rev() {
+            if tracked.is_empty() {
+                break;
+            }
+            let older_number = revision_numbers[i];
+            let older = match Revision::load(commons::revision_path(older_number)) {
+                Ok(older) => older,
+                Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_REVISION_FAILED)),
+            };
+            let older_entry = match older.entries.iter().find(|entry| entry.path == path) {
+                Some(older_entry) => older_entry,
+                None => break,
+            };
+            commited_times.insert(older_number, older.commited);
+
+            if older_entry.hash != hash {
+                let older_text = match load_text(&older_entry.hash)? {
+                    Some(older_text) => older_text,
+                    // Lines can not be older than a binary version.
+                    None => break,
+                };
+                let mut older_indices: HashMap<usize, usize> = HashMap::new();
+                let diff = TextDiff::from_lines(older_text.as_str(), text.as_str());
+                for op in diff.ops() {
+                    if let DiffOp::Equal {
+                        old_index,
+                        new_index,
+                        len,
+                    } = op
+                    {
+                        for j in 0..*len {
+                            older_indices.insert(new_index + j, old_index + j);
+                        }
+                    }
+                }
+                tracked = tracked
+                    .into_iter()
+                    .filter_map(|(line_index, index)| {
+                        older_indices
+                            .get(&index)
+                            .map(|older_index| (line_index, *older_index))
+                    })
+                    .collect();
+                hash = older_entry.hash.clone();
+                text = older_text;
+            }
+            for (line_index, _) in &tracked {
+                lines[*line_index].revision_number = older_number;
+            }
+        }
+
+        Ok(lines)
+    }
+}
+
+/// Returns None if the object is binary.
+fn load_text(hash: &str) -> Result<Option<String>, ZatsuError> {
+    let values = commons::load_object(hash)?;
+    if diff_command::is_binary(&values) {
+        return Ok(None);
+    }
+
+    Ok(Some(String::from_utf8_lossy(&values).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = BlameCommand::new("a.txt", None);
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "one\ntwo\n").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("a.txt", "one\nthree\ntwo\n").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("b.txt", "").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("a.txt", "zero\none\nthree\ntwo\n").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+
+        let command = BlameCommand::new("a.txt", None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        let mut commited_times: HashMap<i32, i64> = HashMap::new();
+        let lines = command.lines(&repository, &mut commited_times).unwrap();
+        let formatted: Vec<String> = lines
+            .iter()
+            .map(|line| format!("{} {}", line.revision_number, line.text))
+            .collect();
+        assert_eq!(vec!["4 zero", "1 one", "2 three", "1 two"], formatted);
+        assert_eq!(4, commited_times.len());
+
+        let command = BlameCommand::new("a.txt", Some(3));
+        let lines = command.lines(&repository, &mut commited_times).unwrap();
+        let numbers: Vec<i32> = lines.iter().map(|line| line.revision_number).collect();
+        assert_eq!(vec![1, 2, 1], numbers);
+        let command = BlameCommand::new("c.txt", None);
+        let result = command.execute();
+        assert!(result.is_err());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+}
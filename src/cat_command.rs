@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use std::io;
+use std::io::Write;
+
+use crate::commons;
+use crate::error;
+use crate::repository::factory;
+use crate::Command;
+use crate::ZatsuError;
+
+pub struct CatCommand {
+    revision_number: i32,
+    path: String,
+}
+
+impl Command for CatCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let stdout = io::stdout();
+        let mut lock = stdout.lock();
+        self.write(&mut lock)
+    }
+}
+
+impl CatCommand {
+    pub fn new(revision_number: i32, path: &str) -> Self {
+        Self {
+            revision_number,
+            path: path.to_string(),
+        }
+    }
+
+    fn write(&self, writer: &mut impl Write) -> Result<(), ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                eprintln!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+        let revision = commons::load_revision(&repository, self.revision_number)?;
+        let path = self.path.strip_prefix("./").unwrap_or(&self.path);
+        let entry = match revision.entries.iter().find(|entry| entry.path == path) {
+            Some(entry) => entry,
+            None => {
+                eprintln!(
+                    "Error: {} not found in revision {}.",
+                    self.path, self.revision_number
+                );
+                return Err(ZatsuError::new(error::CODE_FILE_NOT_FOUND));
+            }
+        };
+
+        let mut reader = commons::object_reader(&entry.hash)?;
+        match io::copy(&mut reader, writer) {
+            Ok(_) => (),
+            // Reading side of the pipe is closed, e.g. by head.
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => (),
+            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+        };
+        match writer.flush() {
+            Ok(()) => (),
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => (),
+            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+        };
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = CatCommand::new(1, "a.txt");
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(1);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = CatCommand::new(1, "a.txt");
+        let mut values: Vec<u8> = Vec::new();
+        let result = command.write(&mut values);
+        assert!(result.is_ok());
+        assert_eq!(b"Hello, World!".to_vec(), values);
+        let command = CatCommand::new(1, "b.txt");
+        let result = command.execute();
+        assert!(result.is_err());
+        let command = CatCommand::new(2, "a.txt");
+        let mut values: Vec<u8> = Vec::new();
+        let result = command.write(&mut values);
+        assert!(result.is_err());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = CatCommand::new(1, "./a.txt");
+        let mut values: Vec<u8> = Vec::new();
+        let result = command.write(&mut values);
+        assert!(result.is_ok());
+        assert_eq!(b"Hello, World!".to_vec(), values);
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+}
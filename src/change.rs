@@ -0,0 +1,431 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use serde::Serializer;
+use serde_derive::Serialize;
+use similar::TextDiff;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::commons;
+use crate::diff_command;
+use crate::entry::EntryKind;
+use crate::Entry;
+use crate::ZatsuError;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+/// A change of an entry between two lists of entries. Entries are serialized
+/// as their hashes.
+#[derive(Serialize, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    #[serde(rename = "old_hash", serialize_with = "serialize_hash")]
+    pub old_entry: Option<Entry>,
+    #[serde(rename = "new_hash", serialize_with = "serialize_hash")]
+    pub new_entry: Option<Entry>,
+}
+
+impl Change {
+    pub fn added(entry: &Entry) -> Self {
+        Self {
+            kind: ChangeKind::Added,
+            path: entry.path.clone(),
+            old_path: None,
+            old_entry: None,
+            new_entry: Some(entry.clone()),
+        }
+    }
+
+    pub fn modified(old_entry: &Entry, new_entry: &Entry) -> Self {
+        Self {
+            kind: ChangeKind::Modified,
+            path: new_entry.path.clone(),
+            old_path: None,
+            old_entry: Some(old_entry.clone()),
+            new_entry: Some(new_entry.clone()),
+        }
+    }
+
+    pub fn deleted(entry: &Entry) -> Self {
+        Self {
+            kind: ChangeKind::Deleted,
+            path: entry.path.clone(),
+            old_path: None,
+            old_entry: Some(entry.clone()),
+            new_entry: None,
+        }
+    }
+
+    pub fn renamed(old_entry: &Entry, new_entry: &Entry) -> Self {
+        Self {
+            kind: ChangeKind::Renamed,
+            path: new_entry.path.clone(),
+            old_path: Some(old_entry.path.clone()),
+            old_entry: Some(old_entry.clone()),
+            new_entry: Some(new_entry.clone()),
+        }
+    }
+
+    /// Returns whether the change, or the old path of a rename, is under any of the paths.
+    /// Every change is contained if no paths are specified.
+    pub fn is_in_paths(&self, paths: &[String]) -> bool {
+        if paths.is_empty() {
+            return true;
+        }
+
+        paths.iter().any(|path| {
+            commons::is_in_path(&self.path, path)
+                || self
+                    .old_path
+                    .as_ref()
+                    .is_some_and(|old_path| commons::is_in_path(old_path, path))
+        })
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match self.kind {
+            ChangeKind::Added => "A",
+            ChangeKind::Modified => "M",
+            ChangeKind::Deleted => "D",
+            ChangeKind::Renamed => "R",
+        };
+        match &self.old_path {
+            Some(old_path) => write!(f, "{} {} -> {}", mark, old_path, self.path),
+            None => write!(f, "{} {}", mark, self.path),
+        }
+    }
+}
+
+fn serialize_hash<S: Serializer>(entry: &Option<Entry>, serializer: S) -> Result<S::Ok, S::Error> {
+    match entry {
+        Some(entry) => serializer.serialize_some(&entry.hash),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Compares entries with previous ones and returns changes sorted by path.
+pub fn compare_entries(entries: &[Entry], previous_entries: &[Entry]) -> Vec<Change> {
+    let divided = divided_entries(entries);
+    let previous_divided = divided_entries(previous_entries);
+    let mut changes: Vec<Change> = Vec::new();
+
+    let keys = divided.keys();
+    for key in keys {
+        if !previous_divided.contains_key(key) {
+            // All entries are appended.
+            let entries = &divided[&key];
+            for entry in entries {
+                changes.push(Change::added(entry));
+            }
+        } else {
+            // Compare entries and add chaned.
+            let entries = &divided[&key];
+            let previous_entries = &previous_divided[&key];
+            update_changes(&mut changes, &entries, &previous_entries);
+        }
+    }
+    let keys = previous_divided.keys();
+    for key in keys {
+        if !divided.contains_key(key) {
+            // All entries are deleted.
+            let entries = &previous_divided[&key];
+            for entry in entries {
+                changes.push(Change::deleted(entry));
+            }
+        }
+    }
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    changes
+}
+
+/// Replaces pairs of deleted and added entries that have the same content with renames.
+pub fn detect_renames(changes: Vec<Change>) -> Vec<Change> {
+    let mut added: Vec<Option<Change>> = Vec::new();
+    let mut others: Vec<Change> = Vec::new();
+    for change in changes {
+        if change.kind == ChangeKind::Added {
+            added.push(Some(change));
+        } else {
+            others.push(change);
+        }
+    }
+
+    let mut result: Vec<Change> = Vec::new();
+    for change in others {
+        if change.kind != ChangeKind::Deleted {
+            result.push(change);
+            continue;
+        }
+        let old_entry = change.old_entry.as_ref().unwrap();
+        let candidates: Vec<usize> = (0..added.len())
+            .filter(|i| {
+                added[*i].as_ref().is_some_and(|added_change| {
+                    let new_entry = added_change.new_entry.as_ref().unwrap();
+                    new_entry.hash == old_entry.hash && new_entry.kind == old_entry.kind
+                })
+            })
+            .collect();
+        // Moving a file into another directory is more likely than renaming it to
+        // another file that happens to have the same content.
+        let file_name = Path::new(&old_entry.path).file_name();
+        let index = candidates
+            .iter()
+            .find(|i| {
+                let new_entry = added[**i].as_ref().unwrap().new_entry.as_ref().unwrap();
+                Path::new(&new_entry.path).file_name() == file_name
+            })
+            .or(candidates.first());
+        match index {
+            Some(index) => {
+                let added_change = added[*index].take().unwrap();
+                let new_entry = added_change.new_entry.as_ref().unwrap();
+                result.push(Change::renamed(old_entry, new_entry));
+            }
+            None => result.push(change),
+        }
+    }
+    result.extend(added.into_iter().flatten());
+    result.sort_by(|a, b| a.path.cmp(&b.path));
+
+    result
+}
+
+/// Replaces pairs of deleted and added text files whose contents are similar at least
+/// the threshold, from 0.0 to 1.0, with renames. New contents are loaded with load_new.
+pub fn detect_similar_renames(
+    changes: Vec<Change>,
+    threshold: f32,
+    load_new: impl Fn(&Entry) -> Result<Vec<u8>, ZatsuError>,
+) -> Result<Vec<Change>, ZatsuError> {
+    let mut added: Vec<(Change, Option<String>)> = Vec::new();
+    let mut deleted: Vec<(Change, Option<String>)> = Vec::new();
+    let mut result: Vec<Change> = Vec::new();
+    for change in changes {
+        match change.kind {
+            ChangeKind::Added => {
+                let entry = change.new_entry.as_ref().unwrap();
+                let text = text_of(entry, load_new(entry)?);
+                added.push((change, text));
+            }
+            ChangeKind::Deleted => {
+                let entry = change.old_entry.as_ref().unwrap();
+                let text = text_of(entry, commons::load_object(&entry.hash)?);
+                deleted.push((change, text));
+            }
+            _ => result.push(change),
+        }
+    }
+
+    let mut used = vec![false; added.len()];
+    for (change, old_text) in deleted {
+        let old_text = match old_text {
+            Some(old_text) => old_text,
+            None => {
+                result.push(change);
+                continue;
+            }
+        };
+        let mut best: Option<(usize, f32)> = None;
+        for (i, (_, new_text)) in added.iter().enumerate() {
+            let new_text = match new_text {
+                Some(new_text) if !used[i] => new_text,
+                _ => continue,
+            };
+            let ratio = TextDiff::from_lines(old_text.as_str(), new_text.as_str()).ratio();
+            if ratio >= threshold && best.is_none_or(|(_, best_ratio)| ratio > best_ratio) {
+                best = Some((i, ratio));
+            }
+        }
+        match best {
+            Some((i, _)) => {
+                used[i] = true;
+                let old_entry = change.old_entry.as_ref().unwrap();
+                let new_entry = added[i].0.new_entry.as_ref().unwrap();
+                result.push(Change::renamed(old_entry, new_entry));
+            }
+            None => result.push(change),
+        }
+    }
+    for (i, (change, _)) in added.into_iter().enumerate() {
+        if !used[i] {
+            result.push(change);
+        }
+    }
+    result.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(result)
+}
+
+fn text_of(entry: &Entry, values: Vec<u8>) -> Option<String> {
+    if entry.kind != EntryKind::File || diff_command::is_binary(&values) {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&values).to_string())
+}
+
+fn find_entry<'a>(entries: &'a [Entry], path: &str) -> Option<&'a Entry> {
+    entries.iter().find(|entry| entry.path == *path)
+}
+
+fn divided_entries(entries: &[Entry]) -> HashMap<char, Vec<Entry>> {
+    let mut result: HashMap<char, Vec<Entry>> = HashMap::new();
+
+    for entry in entries {
+        let key: char;
+        if entry.path.len() > 0 {
+            key = entry.path.chars().nth(0).unwrap();
+        } else {
+            key = char::from_u32(0).unwrap();
+        }
+
+        if !result.contains_key(&key) {
+            result.insert(key, Vec::new());
+        }
+        let entries = result.get_mut(&key).unwrap();
+        entries.push(entry.clone());
+    }
+
+    result
+}
+
+fn update_changes(changes: &mut Vec<Change>, entries: &Vec<Entry>, previous_entries: &Vec<Entry>) {
+    for entry in entries {
+        match find_entry(&previous_entries, &entry.path) {
+            Some(previous_entry) => {
+                // Permission and kind changes are also modifications.
+                if previous_entry.hash != entry.hash
+                    || previous_entry.permission != entry.permission
+                    || previous_entry.kind != entry.kind
+                {
+                    changes.push(Change::modified(previous_entry, entry));
+                }
+            }
+            None => changes.push(Change::added(entry)),
+        }
+    }
+    for entry in previous_entries {
+        let mut found = false;
+        match find_entry(&entries, &entry.path) {
+            Some(_) => {
+                found = true;
+                ()
+            }
+            None => (),
+        }
+        if !found {
+            changes.push(Change::deleted(entry));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, hash: &str, permission: i32) -> Entry {
+        Entry {
+            path: path.to_string(),
+            hash: hash.to_string(),
+            permission,
+            kind: EntryKind::File,
+        }
+    }
+
+    #[test]
+    fn entries_are_comparable() {
+        let previous_entries = vec![
+            entry("a.txt", "01", 0o644),
+            entry("b.txt", "02", 0o644),
+            entry("c.sh", "03", 0o644),
+            entry("dir/d.txt", "04", 0o644),
+        ];
+        let entries = vec![
+            entry("a.txt", "01", 0o644),
+            entry("b.txt", "05", 0o644),
+            entry("c.sh", "03", 0o755),
+            entry("e.txt", "06", 0o644),
+        ];
+        let changes = compare_entries(&entries, &previous_entries);
+        let formatted: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+        assert_eq!(
+            vec!["M b.txt", "M c.sh", "D dir/d.txt", "A e.txt"],
+            formatted
+        );
+        assert_eq!("02", changes[0].old_entry.as_ref().unwrap().hash);
+        assert_eq!("05", changes[0].new_entry.as_ref().unwrap().hash);
+    }
+
+    #[test]
+    fn renames_are_detectable() {
+        let previous_entries = vec![
+            entry("a.txt", "01", 0o644),
+            entry("b.txt", "02", 0o644),
+            entry("dir/c.txt", "03", 0o644),
+        ];
+        let entries = vec![
+            entry("d.txt", "01", 0o644),
+            entry("e.txt", "03", 0o644),
+            entry("new/c.txt", "03", 0o644),
+        ];
+        let changes = compare_entries(&entries, &previous_entries);
+        let changes = detect_renames(changes);
+        let formatted: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+        assert_eq!(
+            vec![
+                "D b.txt",
+                "R a.txt -> d.txt",
+                "A e.txt",
+                "R dir/c.txt -> new/c.txt"
+            ],
+            formatted
+        );
+        assert!(changes[1].is_in_paths(&["a.txt".to_string()]));
+        assert!(!changes[0].is_in_paths(&["a.txt".to_string()]));
+    }
+
+    #[test]
+    fn change_is_serializable() {
+        let change = Change::added(&entry("a.txt", "01", 0o644));
+        let serialized = serde_json::to_string(&change).unwrap();
+        assert_eq!(
+            r#"{"kind":"added","path":"a.txt","old_hash":null,"new_hash":"01"}"#,
+            serialized
+        );
+    }
+}
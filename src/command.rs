@@ -20,8 +20,18 @@
  * DEALINGS IN THE SOFTWARE.
  */
 
+use clap::ValueEnum;
+
 use crate::ZatsuError;
 
+/// Format of outputs for commands that support machine-readable outputs.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
 pub trait Command {
     fn execute(&self) -> Result<(), ZatsuError>;
 }
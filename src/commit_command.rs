@@ -21,14 +21,16 @@
  */
 
 use chrono::Utc;
-use flate2::write::ZlibEncoder;
-use flate2::Compression;
+use std::env;
+use std::env::consts;
 use std::fs;
-use std::io::Write;
-use std::path::Path;
-use std::path::PathBuf;
+use std::process;
 
+use crate::change;
+use crate::commons;
+use crate::entry::EntryKind;
 use crate::error;
+use crate::index::Index;
 use crate::repository::factory;
 use crate::Command;
 use crate::Entry;
@@ -37,7 +39,11 @@ use crate::Repository;
 use crate::Revision;
 use crate::ZatsuError;
 
-pub struct CommitCommand {}
+pub struct CommitCommand {
+    description: String,
+    edit: bool,
+    allow_empty: bool,
+}
 
 impl Command for CommitCommand {
     fn execute(&self) -> Result<(), ZatsuError> {
@@ -49,14 +55,16 @@ impl Command for CommitCommand {
             }
         };
         let latest_revision = repository.latest_revision();
-        let revision_number = latest_revision + 1;
+
+        let previous_index = Index::load_or_new(".zatsu/index.json", repository.version());
+        let mut index = Index::new(repository.version());
 
         let mut producer = FilePathProducer::new(".".to_string());
         let now = Utc::now();
         let mut revision = Revision {
             commited: now.timestamp_millis(),
             entries: Vec::new(),
-            description: "".to_string(),
+            description: self.description.clone(),
         };
         let mut done = false;
         while !done {
@@ -64,15 +72,7 @@ impl Command for CommitCommand {
             if result.is_ok() {
                 let path = result.unwrap();
                 println!("Processing: {}", path);
-                let hash = match process_file(&PathBuf::from(path.clone()), &repository) {
-                    Ok(hash) => hash,
-                    Err(error) => return Err(error),
-                };
-                let entry = Entry {
-                    path: path,
-                    hash: hash,
-                    permission: 0o644,
-                };
+                let entry = process_file(&path, &repository, &previous_index, &mut index, true)?;
                 revision.entries.push(entry);
             } else {
                 let error = result.unwrap_err();
@@ -82,109 +82,145 @@ impl Command for CommitCommand {
             }
         }
 
-        let path = format!(".zatsu/revisions/{:02x}", revision_number & 0xFF).to_string();
-        let a_path = Path::new(&path);
-        let exists = match a_path.try_exists() {
-            Ok(exists) => exists,
-            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-        };
-        if !exists {
-            match fs::create_dir(&path) {
-                Ok(()) => (),
-                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-            };
+        if !self.allow_empty && latest_revision > 0 {
+            let latest = Revision::load(commons::revision_path(latest_revision))?;
+            let changes = change::compare_entries(&revision.entries, &latest.entries);
+            if changes.is_empty() {
+                index.save(".zatsu/index.json")?;
+                println!();
+                println!("Nothing to commit.");
+                return Ok(());
+            }
         }
-        match revision.save(format!("{}/{}.json", &path, revision_number)) {
-            Ok(_) => (),
-            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-        };
-        let mut revision_numbers = repository.revision_numbers();
-        revision_numbers.push(revision_number);
-        repository.set_revision_numbers(&revision_numbers);
-        match repository.save(&Path::new(".zatsu")) {
-            Ok(_) => (),
-            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-        };
+
+        if revision.description.is_empty() && self.edit {
+            revision.description = edit_description()?;
+        }
+
+        let revision_number = commons::save_revision(&mut repository, &revision)?;
+        index.save(".zatsu/index.json")?;
 
         println!("");
         println!("Commited as revision {}.", revision_number);
-        println!("There are {} revision(s).", revision_numbers.len());
+        println!(
+            "There are {} revision(s).",
+            repository.revision_numbers().len()
+        );
 
         Ok(())
     }
 }
 
 impl CommitCommand {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(description: &str, edit: bool, allow_empty: bool) -> Self {
+        Self {
+            description: description.to_string(),
+            edit,
+            allow_empty,
+        }
     }
 }
 
-fn process_file(path: impl AsRef<Path>, repository: &Box<dyn Repository>) -> Result<String, ZatsuError> {
-    let metadata = match fs::metadata(&path) {
+fn edit_description() -> Result<String, ZatsuError> {
+    let path = ".zatsu/DESCRIPTION";
+    let template = "\n# Please enter the description of this revision. Lines starting\n# with '#' will be ignored.\n";
+    match fs::write(path, template) {
+        Ok(()) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+
+    let editor = match env::var("EDITOR") {
+        Ok(editor) if !editor.trim().is_empty() => editor,
+        _ => {
+            if consts::OS == "windows" {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        }
+    };
+    let mut split = editor.split_whitespace();
+    let program = split.next().unwrap_or_default();
+    let status = match process::Command::new(program).args(split).arg(path).status() {
+        Ok(status) => status,
+        Err(_) => return Err(ZatsuError::new(error::CODE_LAUNCHING_EDITOR_FAILED)),
+    };
+    if !status.success() {
+        let _ = fs::remove_file(path);
+        return Err(ZatsuError::new(error::CODE_LAUNCHING_EDITOR_FAILED));
+    }
+
+    let edited = match fs::read_to_string(path) {
+        Ok(edited) => edited,
+        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    };
+    let _ = fs::remove_file(path);
+    let lines: Vec<&str> = edited
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect();
+
+    Ok(lines.join("\n").trim().to_string())
+}
+
+pub fn process_file(
+    path: &str,
+    repository: &Box<dyn Repository>,
+    previous_index: &Index,
+    index: &mut Index,
+    save: bool,
+) -> Result<Entry, ZatsuError> {
+    let metadata = match fs::symlink_metadata(path) {
         Ok(metadata) => metadata,
         Err(_) => return Err(ZatsuError::new(error::CODE_READING_META_DATA_FAILED)),
     };
-    let mut hex_string = String::new();
-    if metadata.is_file() {
-        let values = match fs::read(path) {
-            Ok(values) => values,
-            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
-        };
-        hex_string = repository.object_hash(&values);
-
-        let directory_name = hex_string[0..2].to_string();
-        let path = format!(".zatsu/objects/{}", directory_name).to_string();
-        let a_path = Path::new(&path);
-        let exists = match a_path.try_exists() {
-            Ok(exists) => exists,
-            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-        };
-        if !exists {
-            match fs::create_dir(&path) {
-                Ok(()) => (),
-                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-            };
-        }
+    let mut kind = EntryKind::File;
+    let mut permission = commons::file_permission(&metadata);
+    if metadata.is_symlink() {
+        kind = EntryKind::SymbolicLink;
+        permission = 0o777;
+    }
 
-        let path = format!("{}/{}", &path, hex_string);
-        let a_path = Path::new(&path);
-        let exists = match a_path.try_exists() {
-            Ok(exists) => exists,
-            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-        };
-        if !exists {
-            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
-            match encoder.write_all(&values) {
-                Ok(()) => (),
-                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-            }
-            let compressed = match encoder.finish() {
-                Ok(compressed) => compressed,
-                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-            };
-
-            match fs::write(path, compressed) {
-                Ok(()) => (),
-                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-            };
+    // Reuse the hash if the file is unchanged and its object is still stored.
+    if let Some(hash) = previous_index.find_hash(path, &metadata) {
+        if !save || commons::object_exists(&hash) {
+            index.update(path, &metadata, &hash);
+            return Ok(Entry {
+                path: path.to_string(),
+                hash,
+                permission,
+                kind,
+            });
         }
     }
 
-    Ok(hex_string)
+    let values = commons::load_file(path)?;
+    let hash = repository.object_hash(&values);
+    if save {
+        commons::save_object(&values, &hash)?;
+    }
+    index.update(path, &metadata, &hash);
+
+    Ok(Entry {
+        path: path.to_string(),
+        hash,
+        permission,
+        kind,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use std::env;
+    use std::time::Duration;
+    use std::time::SystemTime;
 
     use crate::InitCommand;
 
     #[test]
     fn is_creatable() {
-        let _command = CommitCommand::new();
+        let _command = CommitCommand::new("", false, false);
     }
 
     #[test]
@@ -193,7 +229,7 @@ mod tests {
         env::set_current_dir("tmp").unwrap();
         let command = InitCommand::new(1);
         command.execute().unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
         let result = command.execute();
         assert!(result.is_ok());
         env::set_current_dir("..").unwrap();
@@ -203,9 +239,75 @@ mod tests {
         env::set_current_dir("tmp").unwrap();
         let command = InitCommand::new(2);
         command.execute().unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
+        let result = command.execute();
+        assert!(result.is_ok());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn is_executable_with_description() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        let command = CommitCommand::new("First revision", false, false);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let revision = Revision::load(".zatsu/revisions/01/1.json").unwrap();
+        assert_eq!("First revision", revision.description);
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn is_executable_with_index() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let file = fs::File::options().write(true).open("a.txt").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        file.set_modified(modified).unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let index = Index::load(".zatsu/index.json").unwrap();
+        assert!(index.entries.contains_key("a.txt"));
+
+        // Same size and modified time, so the cached hash is reused.
+        fs::write("a.txt", "Hello, Zatsu!").unwrap();
+        file.set_modified(modified).unwrap();
+        let command = CommitCommand::new("", false, true);
+        command.execute().unwrap();
+        let revision1 = Revision::load(".zatsu/revisions/01/1.json").unwrap();
+        let revision2 = Revision::load(".zatsu/revisions/02/2.json").unwrap();
+        assert_eq!(revision1.entries[0].hash, revision2.entries[0].hash);
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn is_executable_without_changes() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = CommitCommand::new("", false, false);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert_eq!(vec![1], repository.revision_numbers());
+
+        let command = CommitCommand::new("", false, true);
         let result = command.execute();
         assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert_eq!(vec![1, 2], repository.revision_numbers());
         env::set_current_dir("..").unwrap();
         fs::remove_dir_all("tmp").unwrap();
     }
@@ -20,6 +20,14 @@
  * DEALINGS IN THE SOFTWARE.
  */
 
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use flate2::read;
+use flate2::write::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
 use hex_string::HexString;
@@ -27,11 +35,17 @@ use sha1::Digest;
 use sha1::Sha1;
 use sha2::Sha256;
 use std::fs;
+use std::io;
 use std::io::Write;
+use std::ops::RangeInclusive;
 use std::path::Path;
 
+use crate::entry::EntryKind;
 use crate::error;
 use crate::error::ZatsuError;
+use crate::Entry;
+use crate::Repository;
+use crate::Revision;
 
 pub fn save_object(values: &Vec<u8>, hash: &str) -> Result<(), ZatsuError> {
     let directory_name = hash[0..2].to_string();
@@ -74,6 +88,402 @@ pub fn save_object(values: &Vec<u8>, hash: &str) -> Result<(), ZatsuError> {
     Ok(())
 }
 
+pub fn load_file(path: impl AsRef<Path>) -> Result<Vec<u8>, ZatsuError> {
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(_) => return Err(ZatsuError::new(error::CODE_READING_META_DATA_FAILED)),
+    };
+    if metadata.is_symlink() {
+        // The link target itself is the content of a symbolic link.
+        let target = match fs::read_link(&path) {
+            Ok(target) => target,
+            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+        };
+        return Ok(target.to_string_lossy().as_bytes().to_vec());
+    }
+
+    match fs::read(&path) {
+        Ok(values) => Ok(values),
+        Err(_) => Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    }
+}
+
+#[cfg(unix)]
+pub fn file_permission(metadata: &fs::Metadata) -> i32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    (metadata.permissions().mode() & 0o777) as i32
+}
+
+#[cfg(not(unix))]
+pub fn file_permission(metadata: &fs::Metadata) -> i32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o644
+    }
+}
+
+#[cfg(unix)]
+pub fn set_file_permission(path: impl AsRef<Path>, permission: i32) -> Result<(), ZatsuError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let permissions = fs::Permissions::from_mode((permission & 0o777) as u32);
+    match fs::set_permissions(path, permissions) {
+        Ok(()) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_file_permission(path: impl AsRef<Path>, permission: i32) -> Result<(), ZatsuError> {
+    let mut permissions = match fs::metadata(&path) {
+        Ok(metadata) => metadata.permissions(),
+        Err(_) => return Err(ZatsuError::new(error::CODE_READING_META_DATA_FAILED)),
+    };
+    permissions.set_readonly(permission & 0o222 == 0);
+    match fs::set_permissions(path, permissions) {
+        Ok(()) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+
+    Ok(())
+}
+
+pub fn revision_path(revision_number: i32) -> String {
+    format!(
+        ".zatsu/revisions/{:02x}/{}.json",
+        revision_number & 0xFF,
+        revision_number
+    )
+}
+
+pub fn load_revision(
+    repository: &Box<dyn Repository>,
+    revision_number: i32,
+) -> Result<Revision, ZatsuError> {
+    if !repository.revision_numbers().contains(&revision_number) {
+        eprintln!("Error: revision {} not found.", revision_number);
+        return Err(ZatsuError::new(error::CODE_REVISION_NOT_FOUND));
+    }
+
+    match Revision::load(revision_path(revision_number)) {
+        Ok(revision) => Ok(revision),
+        Err(_) => Err(ZatsuError::new(error::CODE_LOADING_REVISION_FAILED)),
+    }
+}
+
+/// Saves a revision as the next revision number and appends it to the repository.
+pub fn save_revision(
+    repository: &mut Box<dyn Repository>,
+    revision: &Revision,
+) -> Result<i32, ZatsuError> {
+    let revision_number = repository.next_revision();
+    let path = format!(".zatsu/revisions/{:02x}", revision_number & 0xFF).to_string();
+    let a_path = Path::new(&path);
+    let exists = match a_path.try_exists() {
+        Ok(exists) => exists,
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+    if !exists {
+        match fs::create_dir(&path) {
+            Ok(()) => (),
+            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+        };
+    }
+    match revision.save(revision_path(revision_number)) {
+        Ok(_) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+    let mut revision_numbers = repository.revision_numbers();
+    revision_numbers.push(revision_number);
+    repository.set_revision_numbers(&revision_numbers);
+    match repository.save(&Path::new(".zatsu")) {
+        Ok(_) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+
+    Ok(revision_number)
+}
+
+/// Formats milliseconds since the epoch as "YYYY/MM/DD HH:MM" in local time.
+pub fn formatted_time(milliseconds: i64) -> String {
+    let utc_offset = Local::now().offset().local_minus_utc() as i64;
+    let milliseconds = milliseconds + utc_offset * 1000;
+    let time = match DateTime::from_timestamp_millis(milliseconds) {
+        Some(time) => time,
+        None => Utc::now(),
+    };
+
+    time.format("%Y/%m/%d %H:%M").to_string()
+}
+
+/// Parses a time into milliseconds since the epoch. RFC 3339, "YYYY-MM-DD",
+/// "YYYY-MM-DD HH:MM[:SS]" in local time and seconds since the epoch are accepted.
+pub fn parse_time(string: &str) -> Result<i64, ZatsuError> {
+    let string = string.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(string) {
+        return Ok(date_time.timestamp_millis());
+    }
+    if let Ok(seconds) = string.parse::<i64>() {
+        return Ok(seconds * 1000);
+    }
+
+    let mut naive_date_time: Option<NaiveDateTime> = None;
+    for format in [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(string, format) {
+            naive_date_time = Some(date_time);
+            break;
+        }
+    }
+    if naive_date_time.is_none() {
+        if let Ok(date) = NaiveDate::parse_from_str(string, "%Y-%m-%d") {
+            naive_date_time = date.and_hms_opt(0, 0, 0);
+        }
+    }
+
+    match naive_date_time.and_then(|date_time| Local.from_local_datetime(&date_time).earliest()) {
+        Some(date_time) => Ok(date_time.timestamp_millis()),
+        None => Err(ZatsuError::with_details(
+            error::CODE_PARSING_TIME_FAILED,
+            string.to_string(),
+        )),
+    }
+}
+
+/// Parses a duration like "90d", "12h" or "1y6m" into milliseconds. Units are h for hours,
+/// d for days, w for weeks, m for months of 30 days and y for years of 365 days.
+pub fn parse_duration(string: &str) -> Result<i64, ZatsuError> {
+    let error = || ZatsuError::with_details(error::CODE_PARSING_TIME_FAILED, string.to_string());
+    let hour: i64 = 60 * 60 * 1000;
+    let mut milliseconds = 0;
+    let mut number = String::new();
+    for character in string.trim().chars() {
+        if character.is_ascii_digit() {
+            number.push(character);
+            continue;
+        }
+        let value: i64 = number.parse().map_err(|_| error())?;
+        let unit = match character {
+            'h' => hour,
+            'd' => 24 * hour,
+            'w' => 7 * 24 * hour,
+            'm' => 30 * 24 * hour,
+            'y' => 365 * 24 * hour,
+            _ => return Err(error()),
+        };
+        milliseconds = value
+            .checked_mul(unit)
+            .and_then(|value| value.checked_add(milliseconds))
+            .ok_or_else(error)?;
+        number.clear();
+    }
+    if !number.is_empty() || string.trim().is_empty() {
+        return Err(error());
+    }
+
+    Ok(milliseconds)
+}
+
+/// Parses a range of revision numbers like "N", "N..M", "N.." or "..M". Both ends are inclusive.
+pub fn parse_range(string: &str) -> Result<RangeInclusive<i32>, ZatsuError> {
+    let error = || ZatsuError::with_details(error::CODE_PARSING_RANGE_FAILED, string.to_string());
+    let string = string.trim();
+    let (start, end) = match string.split_once("..") {
+        Some((start, end)) => (start.trim(), end.trim()),
+        None => (string, string),
+    };
+    let start = match start {
+        "" => i32::MIN,
+        start => start.parse().map_err(|_| error())?,
+    };
+    let end = match end {
+        "" => i32::MAX,
+        end => end.parse().map_err(|_| error())?,
+    };
+    if start > end {
+        return Err(error());
+    }
+
+    Ok(start..=end)
+}
+
+/// Parses a size like "5GiB", "500MB" or "1024" into bytes. K, M, G and T without "B"
+/// are binary units.
+pub fn parse_size(string: &str) -> Result<u64, ZatsuError> {
+    let error = || ZatsuError::with_details(error::CODE_PARSING_SIZE_FAILED, string.to_string());
+    let string = string.trim();
+    let position = string
+        .find(|character: char| !character.is_ascii_digit() && character != '.')
+        .unwrap_or(string.len());
+    let (number, unit) = string.split_at(position);
+    let number: f64 = number.parse().map_err(|_| error())?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        _ => return Err(error()),
+    };
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Formats bytes with a binary unit, like "1.5 GiB".
+pub fn formatted_size(size: u64) -> String {
+    let units = ["bytes", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = size as f64;
+    let mut index = 0;
+    while value >= 1024.0 && index < units.len() - 1 {
+        value /= 1024.0;
+        index += 1;
+    }
+    if index == 0 {
+        return format!("{} {}", size, units[0]);
+    }
+
+    format!("{:.1} {}", value, units[index])
+}
+
+/// Returns whether the specified path means the root, such as "." or "/".
+pub fn is_root_path(specified_path: &str) -> bool {
+    let specified_path = specified_path.trim_end_matches('/');
+
+    specified_path.is_empty() || specified_path == "."
+}
+
+/// Returns whether the path is the specified path itself or under it.
+/// Both "." and "/" mean the root, which contains every path.
+pub fn is_in_path(path: &str, specified_path: &str) -> bool {
+    if is_root_path(specified_path) {
+        return true;
+    }
+    let specified_path = specified_path.trim_end_matches('/');
+    let specified_path = specified_path.strip_prefix("./").unwrap_or(specified_path);
+
+    path == specified_path || path.starts_with(&format!("{}/", specified_path))
+}
+
+pub fn object_exists(hash: &str) -> bool {
+    let directory_name = hash[0..2].to_string();
+    let path = format!(".zatsu/objects/{}/{}", directory_name, hash);
+
+    Path::new(&path).exists()
+}
+
+pub fn object_reader(hash: &str) -> Result<read::ZlibDecoder<fs::File>, ZatsuError> {
+    let directory_name = hash[0..2].to_string();
+    let path = format!(".zatsu/objects/{}/{}", directory_name, hash);
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    };
+
+    Ok(read::ZlibDecoder::new(file))
+}
+
+/// Returns the decompressed size of an object.
+pub fn object_size(hash: &str) -> Result<u64, ZatsuError> {
+    let mut reader = object_reader(hash)?;
+    match io::copy(&mut reader, &mut io::sink()) {
+        Ok(size) => Ok(size),
+        Err(_) => Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    }
+}
+
+pub fn load_object(hash: &str) -> Result<Vec<u8>, ZatsuError> {
+    let directory_name = hash[0..2].to_string();
+    let path = format!(".zatsu/objects/{}/{}", directory_name, hash);
+    let values = match fs::read(path) {
+        Ok(values) => values,
+        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    };
+    let mut decoder = ZlibDecoder::new(Vec::new());
+    match decoder.write_all(&values) {
+        Ok(()) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    };
+    let decoded = match decoder.finish() {
+        Ok(decoded) => decoded,
+        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    };
+
+    Ok(decoded)
+}
+
+/// Writes the content of an entry into the path with its kind and permission.
+pub fn write_entry(entry: &Entry, path: impl AsRef<Path>) -> Result<(), ZatsuError> {
+    let values = load_object(&entry.hash)?;
+    if entry.kind == EntryKind::SymbolicLink {
+        let target = String::from_utf8_lossy(&values).to_string();
+        return create_symbolic_link(&target, path);
+    }
+
+    // Do not write through an existing symbolic link, and make an existing read-only
+    // file writable. The stored permission is applied after writing.
+    if let Ok(metadata) = fs::symlink_metadata(&path) {
+        if metadata.is_symlink() {
+            match fs::remove_file(&path) {
+                Ok(()) => (),
+                Err(_) => return Err(ZatsuError::new(error::CODE_REMOVING_FILE_FAILED)),
+            };
+        } else if metadata.permissions().readonly() {
+            set_file_permission(&path, file_permission(&metadata) | 0o200)?;
+        }
+    }
+    match fs::write(&path, values) {
+        Ok(()) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+    set_file_permission(path, entry.permission)?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn create_symbolic_link(target: &str, path: impl AsRef<Path>) -> Result<(), ZatsuError> {
+    if fs::symlink_metadata(&path).is_ok() {
+        match fs::remove_file(&path) {
+            Ok(()) => (),
+            Err(_) => return Err(ZatsuError::new(error::CODE_REMOVING_FILE_FAILED)),
+        };
+    }
+    match std::os::unix::fs::symlink(target, path) {
+        Ok(()) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn create_symbolic_link(target: &str, path: impl AsRef<Path>) -> Result<(), ZatsuError> {
+    if fs::symlink_metadata(&path).is_ok() {
+        match fs::remove_file(&path) {
+            Ok(()) => (),
+            Err(_) => return Err(ZatsuError::new(error::CODE_REMOVING_FILE_FAILED)),
+        };
+    }
+    match std::os::windows::fs::symlink_file(target, path) {
+        Ok(()) => (),
+        Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    };
+
+    Ok(())
+}
+
 pub fn object_hash(values: &Vec<u8>, version: i32) -> String {
     let result: String;
     if version <= 1 {
@@ -129,6 +539,85 @@ mod tests {
         fs::remove_dir_all("tmp").unwrap();
     }
 
+    #[test]
+    fn object_is_loadable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        let string = "Hello, World!".to_string();
+        let values = string.into_bytes();
+        save_object(&values, "12345678").unwrap();
+        let result = load_object("12345678");
+        assert_eq!(values, result.unwrap());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn time_is_parsable() {
+        assert_eq!(
+            1_700_000_000_000,
+            parse_time("2023-11-14T22:13:20Z").unwrap()
+        );
+        assert_eq!(1_700_000_000_000, parse_time("1700000000").unwrap());
+        let local = Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 0).unwrap();
+        assert_eq!(
+            local.timestamp_millis(),
+            parse_time("2024-01-02 03:04").unwrap()
+        );
+        let local = Local.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        assert_eq!(local.timestamp_millis(), parse_time("2024-01-02").unwrap());
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn duration_is_parsable() {
+        let day = 24 * 60 * 60 * 1000;
+        assert_eq!(90 * day, parse_duration("90d").unwrap());
+        assert_eq!(day / 2, parse_duration("12h").unwrap());
+        assert_eq!(365 * day + 6 * 30 * day, parse_duration("1y6m").unwrap());
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("99999999999999999w").is_err());
+    }
+
+    #[test]
+    fn range_is_parsable() {
+        assert_eq!(17..=17, parse_range("17").unwrap());
+        assert_eq!(10..=20, parse_range("10..20").unwrap());
+        assert_eq!(10..=i32::MAX, parse_range("10..").unwrap());
+        assert_eq!(i32::MIN..=20, parse_range("..20").unwrap());
+        assert!(parse_range("20..10").is_err());
+        assert!(parse_range("a..b").is_err());
+    }
+
+    #[test]
+    fn size_is_parsable() {
+        assert_eq!(1024, parse_size("1024").unwrap());
+        assert_eq!(5 * 1024 * 1024 * 1024, parse_size("5GiB").unwrap());
+        assert_eq!(500_000_000, parse_size("500MB").unwrap());
+        assert_eq!(1536, parse_size("1.5K").unwrap());
+        assert!(parse_size("5 apples").is_err());
+        assert_eq!("512 bytes", formatted_size(512));
+        assert_eq!("1.5 GiB", formatted_size(1536 * 1024 * 1024));
+    }
+
+    #[test]
+    fn path_is_checkable() {
+        assert!(is_in_path("src/main.rs", "src"));
+        assert!(is_in_path("src/main.rs", "src/"));
+        assert!(is_in_path("src/main.rs", "./src"));
+        assert!(is_in_path("src/main.rs", "src/main.rs"));
+        assert!(is_in_path("src/main.rs", "."));
+        assert!(is_in_path("src/main.rs", "/"));
+        assert!(!is_in_path("srcfoo/x.rs", "src"));
+        assert!(!is_in_path("src", "src/main.rs"));
+        assert!(is_root_path("./"));
+        assert!(!is_root_path("src"));
+    }
+
     #[test]
     fn object_hash_is_calculatable() {
         let string = "Hello, World!".to_string();
@@ -0,0 +1,210 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use similar::TextDiff;
+
+use crate::change;
+use crate::change::Change;
+use crate::commons;
+use crate::error;
+use crate::repository::factory;
+use crate::status_command;
+use crate::Command;
+use crate::Entry;
+use crate::ZatsuError;
+
+pub struct DiffCommand {
+    revision_numbers: Vec<i32>,
+    paths: Vec<String>,
+    similarity: Option<u8>,
+}
+
+impl Command for DiffCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+
+        // Without the second revision, current files are compared.
+        let old_revision_number = match self.revision_numbers.first() {
+            Some(revision_number) => *revision_number,
+            None => repository.latest_revision(),
+        };
+        let new_revision_number = self.revision_numbers.get(1).copied();
+
+        let mut old_entries: Vec<Entry> = Vec::new();
+        if old_revision_number > 0 {
+            old_entries = commons::load_revision(&repository, old_revision_number)?.entries;
+        }
+        let new_entries = match new_revision_number {
+            Some(revision_number) => commons::load_revision(&repository, revision_number)?.entries,
+            None => status_command::working_entries(&repository)?,
+        };
+
+        let working = new_revision_number.is_none();
+        let changes = change::compare_entries(&new_entries, &old_entries);
+        let mut changes = change::detect_renames(changes);
+        if let Some(similarity) = self.similarity {
+            changes =
+                change::detect_similar_renames(changes, similarity as f32 / 100.0, |entry| {
+                    if working {
+                        commons::load_file(&entry.path)
+                    } else {
+                        commons::load_object(&entry.hash)
+                    }
+                })?;
+        }
+        for change in &changes {
+            if !change.is_in_paths(&self.paths) {
+                continue;
+            }
+            print_change(change, working)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DiffCommand {
+    pub fn new(revision_numbers: &[i32], paths: &[String], similarity: Option<u8>) -> Self {
+        Self {
+            revision_numbers: revision_numbers.to_vec(),
+            paths: paths.to_vec(),
+            similarity,
+        }
+    }
+}
+
+fn print_change(change: &Change, working: bool) -> Result<(), ZatsuError> {
+    println!("{}", change);
+
+    let mut old_values: Vec<u8> = Vec::new();
+    let mut old_label = "/dev/null".to_string();
+    if let Some(entry) = &change.old_entry {
+        old_values = commons::load_object(&entry.hash)?;
+        old_label = format!("a/{}", entry.path);
+    }
+    let mut new_values: Vec<u8> = Vec::new();
+    let mut new_label = "/dev/null".to_string();
+    if let Some(entry) = &change.new_entry {
+        if working {
+            new_values = commons::load_file(&entry.path)?;
+        } else {
+            new_values = commons::load_object(&entry.hash)?;
+        }
+        new_label = format!("b/{}", entry.path);
+    }
+
+    if let (Some(old_entry), Some(new_entry)) = (&change.old_entry, &change.new_entry) {
+        if old_entry.permission != new_entry.permission {
+            println!(
+                "Permission: {:o} -> {:o}",
+                old_entry.permission, new_entry.permission
+            );
+        }
+    }
+
+    if old_values == new_values {
+        println!();
+        return Ok(());
+    }
+    if is_binary(&old_values) || is_binary(&new_values) {
+        println!("Binary files {} and {} differ", old_label, new_label);
+        println!();
+        return Ok(());
+    }
+
+    let old_text = String::from_utf8_lossy(&old_values);
+    let new_text = String::from_utf8_lossy(&new_values);
+    let diff = TextDiff::from_lines(old_text.as_ref(), new_text.as_ref());
+    print!("{}", diff.unified_diff().header(&old_label, &new_label));
+    println!();
+
+    Ok(())
+}
+
+/// Treats values as binary if they contain a NUL byte in the first 8000 bytes, like Git does.
+/// Other encodings than UTF-8, such as Latin-1, are treated as text.
+pub fn is_binary(values: &[u8]) -> bool {
+    let length = values.len().min(8000);
+    values[..length].contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = DiffCommand::new(&[1, 2], &["a.txt".to_string()], None);
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(1);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!\n").unwrap();
+        fs::write("b.dat", [0u8, 1, 2]).unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, Zatsu!\n").unwrap();
+        fs::write("b.dat", [0u8, 1, 3]).unwrap();
+        let command = DiffCommand::new(&[], &[], None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = DiffCommand::new(&[1, 2], &["a.txt".to_string()], None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        fs::rename("a.txt", "c.txt").unwrap();
+        fs::write("c.txt", "Hello, Zatsu!\nHello, World!\n").unwrap();
+        let command = DiffCommand::new(&[], &["a.txt".to_string()], Some(50));
+        let result = command.execute();
+        assert!(result.is_ok());
+        let command = DiffCommand::new(&[1, 3], &[], None);
+        let result = command.execute();
+        assert!(result.is_err());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn binary_is_detectable() {
+        assert!(!is_binary(b"Hello, World!\n"));
+        assert!(is_binary(&[0, 1, 2]));
+        assert!(!is_binary("caf\u{e9}".as_bytes()));
+        assert!(!is_binary(&[b'c', b'a', b'f', 0xe9]));
+    }
+}
@@ -23,10 +23,18 @@
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum EntryKind {
+    #[default]
+    File,
+    SymbolicLink,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Entry {
     pub path: String,
     pub hash: String,
-    // TODO: Use this reserved field.
     pub permission: i32,
+    #[serde(default)]
+    pub kind: EntryKind,
 }
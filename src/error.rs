@@ -41,6 +41,13 @@ pub const CODE_DESERIALIZATION_FAILED: i32 = 12;
 pub const CODE_SERIALIZATION_FAILED: i32 = 13;
 pub const CODE_REMOVING_FILE_FAILED: i32 = 14;
 pub const CODE_REMOVING_DIRECTORY_FAILED: i32 = 15;
+pub const CODE_LAUNCHING_EDITOR_FAILED: i32 = 16;
+pub const CODE_UNCOMMITTED_CHANGES_FOUND: i32 = 17;
+pub const CODE_FILE_ALREADY_EXISTS: i32 = 18;
+pub const CODE_PARSING_TIME_FAILED: i32 = 19;
+pub const CODE_PARSING_RANGE_FAILED: i32 = 20;
+pub const CODE_PARSING_PATTERN_FAILED: i32 = 21;
+pub const CODE_PARSING_SIZE_FAILED: i32 = 22;
 
 #[derive(Debug)]
 pub struct ZatsuError {
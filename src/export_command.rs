@@ -0,0 +1,362 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::Timelike;
+use clap::ValueEnum;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io;
+use std::io::Write;
+use zip::write::SimpleFileOptions;
+use zip::CompressionMethod;
+use zip::ZipWriter;
+
+use crate::commons;
+use crate::entry::EntryKind;
+use crate::error;
+use crate::repository::factory;
+use crate::Command;
+use crate::Entry;
+use crate::ZatsuError;
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Tar,
+    #[value(name = "tar.gz")]
+    TarGz,
+    Zip,
+}
+
+pub struct ExportCommand {
+    revision_number: i32,
+    path: String,
+    format: Option<ExportFormat>,
+    output: String,
+}
+
+impl Command for ExportCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+        let revision = commons::load_revision(&repository, self.revision_number)?;
+        let entries: Vec<&Entry> = revision
+            .entries
+            .iter()
+            .filter(|entry| commons::is_in_path(&entry.path, &self.path))
+            .collect();
+        // An empty revision is exported as an empty archive.
+        if entries.is_empty() && !commons::is_root_path(&self.path) {
+            eprintln!(
+                "Error: {} not found in revision {}.",
+                self.path, self.revision_number
+            );
+            return Err(ZatsuError::new(error::CODE_FILE_NOT_FOUND));
+        }
+
+        let writer: Box<dyn Write> = if self.output == "-" {
+            Box::new(io::stdout().lock())
+        } else {
+            match fs::File::create(&self.output) {
+                Ok(file) => Box::new(io::BufWriter::new(file)),
+                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+            }
+        };
+
+        // Tar archives have modified times in seconds.
+        let modified = (revision.commited / 1000).max(0) as u64;
+        match self.format() {
+            ExportFormat::Tar => {
+                let writer = write_tar(writer, &entries, modified)?;
+                finish(writer)?;
+            }
+            ExportFormat::TarGz => {
+                let encoder = GzEncoder::new(writer, Compression::default());
+                let encoder = write_tar(encoder, &entries, modified)?;
+                let writer = match encoder.finish() {
+                    Ok(writer) => writer,
+                    Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+                };
+                finish(writer)?;
+            }
+            ExportFormat::Zip => {
+                let writer = write_zip(writer, &entries, revision.commited)?;
+                finish(writer)?;
+            }
+        }
+
+        if self.output != "-" {
+            println!(
+                "{} file(s) of revision {} exported to {}.",
+                entries.len(),
+                self.revision_number,
+                self.output
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl ExportCommand {
+    pub fn new(
+        revision_number: i32,
+        path: &str,
+        format: Option<ExportFormat>,
+        output: &str,
+    ) -> Self {
+        Self {
+            revision_number,
+            path: path.to_string(),
+            format,
+            output: output.to_string(),
+        }
+    }
+
+    /// Returns the specified format, or guesses it from the output file name.
+    fn format(&self) -> ExportFormat {
+        if let Some(format) = self.format {
+            return format;
+        }
+
+        if self.output.ends_with(".tar.gz") || self.output.ends_with(".tgz") {
+            ExportFormat::TarGz
+        } else if self.output.ends_with(".zip") {
+            ExportFormat::Zip
+        } else {
+            ExportFormat::Tar
+        }
+    }
+}
+
+fn write_tar<W: Write>(writer: W, entries: &[&Entry], modified: u64) -> Result<W, ZatsuError> {
+    let mut builder = tar::Builder::new(writer);
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode((entry.permission & 0o777) as u32);
+        header.set_mtime(modified);
+        if entry.kind == EntryKind::SymbolicLink {
+            let values = commons::load_object(&entry.hash)?;
+            let target = String::from_utf8_lossy(&values).to_string();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            match builder.append_link(&mut header, archive_path(&entry.path), target) {
+                Ok(()) => (),
+                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+            };
+        } else {
+            // Decompress twice not to hold a whole large file in memory.
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(commons::object_size(&entry.hash)?);
+            let reader = commons::object_reader(&entry.hash)?;
+            match builder.append_data(&mut header, archive_path(&entry.path), reader) {
+                Ok(()) => (),
+                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+            };
+        }
+    }
+
+    match builder.into_inner() {
+        Ok(writer) => Ok(writer),
+        Err(_) => Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    }
+}
+
+fn write_zip<W: Write>(writer: W, entries: &[&Entry], commited: i64) -> Result<W, ZatsuError> {
+    let mut writer = ZipWriter::new_stream(writer);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(zip_date_time(commited));
+    for entry in entries {
+        let options = options.unix_permissions((entry.permission & 0o777) as u32);
+        let path = archive_path(&entry.path);
+        if entry.kind == EntryKind::SymbolicLink {
+            let values = commons::load_object(&entry.hash)?;
+            let target = String::from_utf8_lossy(&values).to_string();
+            match writer.add_symlink(path, target, options) {
+                Ok(()) => (),
+                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+            };
+        } else {
+            let size = commons::object_size(&entry.hash)?;
+            let options = options.large_file(size > u32::MAX as u64);
+            match writer.start_file(path, options) {
+                Ok(()) => (),
+                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+            };
+            let mut reader = commons::object_reader(&entry.hash)?;
+            match io::copy(&mut reader, &mut writer) {
+                Ok(_) => (),
+                Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+            };
+        }
+    }
+
+    match writer.finish() {
+        Ok(writer) => Ok(writer.into_inner()),
+        Err(_) => Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    }
+}
+
+/// Zip archives have modified times in local time without time zones.
+fn zip_date_time(commited: i64) -> zip::DateTime {
+    let date_time = match DateTime::from_timestamp_millis(commited) {
+        Some(date_time) => date_time.with_timezone(&Local),
+        None => return zip::DateTime::default(),
+    };
+    zip::DateTime::from_date_and_time(
+        date_time.year() as u16,
+        date_time.month() as u8,
+        date_time.day() as u8,
+        date_time.hour() as u8,
+        date_time.minute() as u8,
+        date_time.second() as u8,
+    )
+    .unwrap_or_default()
+}
+
+/// Normalizes a path in the same way as FilePathProducer.
+fn archive_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+fn finish(mut writer: Box<dyn Write>) -> Result<(), ZatsuError> {
+    match writer.flush() {
+        Ok(()) => Ok(()),
+        Err(_) => Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use flate2::read::GzDecoder;
+    use std::env;
+    use std::io::Read;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+    use crate::Revision;
+
+    #[test]
+    fn is_creatable() {
+        let _command = ExportCommand::new(1, ".", Some(ExportFormat::Tar), "out.tar");
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::create_dir("dir").unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        fs::write("dir/b.txt", "Hello, Zatsu!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let revision = Revision::load(".zatsu/revisions/01/1.json").unwrap();
+        let entry = revision
+            .entries
+            .iter()
+            .find(|entry| entry.path == "dir/b.txt")
+            .unwrap();
+
+        let command = ExportCommand::new(1, "dir", None, "out.tar.gz");
+        let result = command.execute();
+        assert!(result.is_ok());
+        let file = fs::File::open("out.tar.gz").unwrap();
+        let mut archive = tar::Archive::new(GzDecoder::new(file));
+        let mut count = 0;
+        for result in archive.entries().unwrap() {
+            let mut tar_entry = result.unwrap();
+            assert_eq!("dir/b.txt", tar_entry.path().unwrap().to_string_lossy());
+            assert_eq!(entry.permission as u32, tar_entry.header().mode().unwrap());
+            assert_eq!(
+                (revision.commited / 1000) as u64,
+                tar_entry.header().mtime().unwrap()
+            );
+            let mut string = String::new();
+            tar_entry.read_to_string(&mut string).unwrap();
+            assert_eq!("Hello, Zatsu!", string);
+            count += 1;
+        }
+        assert_eq!(1, count);
+
+        let command = ExportCommand::new(1, ".", Some(ExportFormat::Tar), "out.tar");
+        let result = command.execute();
+        assert!(result.is_ok());
+        let file = fs::File::open("out.tar").unwrap();
+        let mut archive = tar::Archive::new(file);
+        assert_eq!(2, archive.entries().unwrap().count());
+
+        let command = ExportCommand::new(1, ".", None, "out.zip");
+        let result = command.execute();
+        assert!(result.is_ok());
+        let file = fs::File::open("out.zip").unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        assert_eq!(2, archive.len());
+        let mut file = archive.by_name("dir/b.txt").unwrap();
+        assert_eq!(
+            Some(entry.permission as u32),
+            file.unix_mode().map(|mode| mode & 0o777)
+        );
+        assert_eq!(Some(zip_date_time(revision.commited)), file.last_modified());
+        let mut string = String::new();
+        file.read_to_string(&mut string).unwrap();
+        assert_eq!("Hello, Zatsu!", string);
+
+        for path in ["a.txt", "out.tar.gz", "out.tar", "out.zip"] {
+            fs::remove_file(path).unwrap();
+        }
+        fs::remove_dir_all("dir").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = ExportCommand::new(2, ".", Some(ExportFormat::Tar), "out.tar");
+        let result = command.execute();
+        assert!(result.is_ok());
+        let file = fs::File::open("out.tar").unwrap();
+        let mut archive = tar::Archive::new(file);
+        assert_eq!(0, archive.entries().unwrap().count());
+        let command = ExportCommand::new(2, "dir", Some(ExportFormat::Tar), "out.tar");
+        let result = command.execute();
+        assert!(result.is_err());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn path_is_normalizable() {
+        assert_eq!("dir/a.txt", archive_path("./dir/a.txt"));
+        assert_eq!("dir/a.txt", archive_path("dir\\a.txt"));
+    }
+}
@@ -20,6 +20,9 @@
  * DEALINGS IN THE SOFTWARE.
  */
 
+use ignore::gitignore::Gitignore;
+use ignore::Match;
+use std::collections::HashMap;
 use std::env::consts;
 use std::fs;
 use std::path::Path;
@@ -31,6 +34,7 @@ pub struct FilePathProducer {
     file_paths: Vec<String>,
     directory_paths: Vec<String>,
     prefix_length: usize,
+    ignores: HashMap<String, Gitignore>,
 }
 
 impl FilePathProducer {
@@ -41,6 +45,7 @@ impl FilePathProducer {
             file_paths: Vec::new(),
             directory_paths: vec![path],
             prefix_length: prefix_length,
+            ignores: HashMap::new(),
         };
     }
 
@@ -74,6 +79,15 @@ impl FilePathProducer {
             }
 
             if scan {
+                let ignore_path = Path::new(&directory_path).join(".zatsuignore");
+                if ignore_path.is_file() {
+                    let (gitignore, error) = Gitignore::new(&ignore_path);
+                    if let Some(error) = error {
+                        eprintln!("Warning: {}", error);
+                    }
+                    self.ignores.insert(directory_path.clone(), gitignore);
+                }
+
                 let read_dir = match fs::read_dir(directory_path) {
                     Ok(read_dir) => read_dir,
                     Err(_) => return Err(ZatsuError::new(error::CODE_READING_DIRECTORY_FAILED)),
@@ -82,17 +96,21 @@ impl FilePathProducer {
                     if result.is_ok() {
                         let entry = result.unwrap();
 
-                        let metadata = match fs::metadata(entry.path()) {
+                        // Symbolic links are produced as files and never followed.
+                        let metadata = match fs::symlink_metadata(entry.path()) {
                             Ok(metadata) => metadata,
                             Err(_) => {
                                 return Err(ZatsuError::new(error::CODE_READING_META_DATA_FAILED))
                             }
                         };
                         let path = entry.path().to_string_lossy().to_string();
-                        if metadata.is_file() {
+                        if self.is_ignored(&path, metadata.is_dir()) {
+                            continue;
+                        }
+                        if metadata.is_file() || metadata.is_symlink() {
                             let path = path[self.prefix_length..].to_string();
                             self.file_paths.push(path);
-                        } else {
+                        } else if metadata.is_dir() {
                             self.directory_paths.push(path);
                         }
                     }
@@ -102,12 +120,30 @@ impl FilePathProducer {
 
         Err(ZatsuError::new(error::CODE_PRODUCING_FINISHED))
     }
+
+    fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        // Deeper .zatsuignore files take precedence over shallower ones.
+        for ancestor in Path::new(path).ancestors().skip(1) {
+            let key = ancestor.to_string_lossy().to_string();
+            if let Some(gitignore) = self.ignores.get(&key) {
+                match gitignore.matched(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => (),
+                }
+            }
+        }
+
+        false
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::env;
+
     #[test]
     fn is_creatable() {
         let producer = FilePathProducer::new(".".to_string());
@@ -127,4 +163,40 @@ mod tests {
             };
         }
     }
+
+    #[test]
+    fn is_ignorable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        fs::create_dir_all("target/debug").unwrap();
+        fs::create_dir_all("src/generated").unwrap();
+        fs::write(".zatsuignore", "/target/\n*.swp\n!keep.swp\n").unwrap();
+        fs::write("src/.zatsuignore", "generated/\n").unwrap();
+        fs::write("target/debug/a.out", "").unwrap();
+        fs::write("a.txt", "").unwrap();
+        fs::write("a.txt.swp", "").unwrap();
+        fs::write("keep.swp", "").unwrap();
+        fs::write("src/main.rs", "").unwrap();
+        fs::write("src/b.swp", "").unwrap();
+        fs::write("src/generated/c.rs", "").unwrap();
+
+        let mut producer = FilePathProducer::new(".".to_string());
+        let mut paths: Vec<String> = Vec::new();
+        while let Ok(path) = producer.next() {
+            paths.push(path);
+        }
+        paths.sort();
+        assert_eq!(
+            vec![
+                ".zatsuignore",
+                "a.txt",
+                "keep.swp",
+                "src/.zatsuignore",
+                "src/main.rs"
+            ],
+            paths
+        );
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
 }
@@ -20,10 +20,19 @@
  * DEALINGS IN THE SOFTWARE.
  */
 
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::Utc;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs;
+use std::ops::RangeInclusive;
 use std::path::Path;
 use std::path::PathBuf;
 
+use crate::commons;
 use crate::error;
 use crate::repository::factory;
 use crate::Command;
@@ -31,8 +40,31 @@ use crate::Repository;
 use crate::Revision;
 use crate::ZatsuError;
 
+/// Counts of revisions to keep for each policy. The newest revision of each hour,
+/// day, week, month or year is kept, up to the count, like restic does.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct KeepPolicy {
+    pub last: Option<usize>,
+    pub hourly: Option<usize>,
+    pub daily: Option<usize>,
+    pub weekly: Option<usize>,
+    pub monthly: Option<usize>,
+    pub yearly: Option<usize>,
+}
+
 pub struct ForgetCommand {
-    revision_count: i32,
+    policy: KeepPolicy,
+    max_size: Option<String>,
+    revision_numbers: Vec<i32>,
+    ranges: Vec<String>,
+    older_than: Option<String>,
+}
+
+/// Sizes of revision files and objects that revisions use, to project freed bytes.
+struct SizeModel {
+    revision_sizes: HashMap<i32, u64>,
+    object_sizes: HashMap<String, u64>,
+    hashes: HashMap<i32, HashSet<String>>,
 }
 
 impl Command for ForgetCommand {
@@ -44,25 +76,304 @@ impl Command for ForgetCommand {
                 return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
             }
         };
-        let mut revision_numbers = repository.revision_numbers();
-        let current_count = revision_numbers.len() as i32;
-        let removed_count = current_count - self.revision_count;
-        if removed_count <= 0 {
+        let max_size = match &self.max_size {
+            Some(max_size) => match commons::parse_size(max_size) {
+                Ok(max_size) => Some(max_size),
+                Err(error) => {
+                    println!("Error: invalid size {}.", max_size);
+                    return Err(error);
+                }
+            },
+            None => None,
+        };
+
+        let revision_numbers = repository.revision_numbers();
+        for revision_number in &self.revision_numbers {
+            if !revision_numbers.contains(revision_number) {
+                println!("Error: revision {} not found.", revision_number);
+                return Err(ZatsuError::new(error::CODE_REVISION_NOT_FOUND));
+            }
+        }
+        let mut ranges: Vec<RangeInclusive<i32>> = Vec::new();
+        for range in &self.ranges {
+            match commons::parse_range(range) {
+                Ok(range) => ranges.push(range),
+                Err(error) => {
+                    println!("Error: invalid revision range {}.", range);
+                    return Err(error);
+                }
+            };
+        }
+        let older_than = match &self.older_than {
+            Some(older_than) => match commons::parse_duration(older_than) {
+                Ok(duration) => Some(Utc::now().timestamp_millis().saturating_sub(duration)),
+                Err(error) => {
+                    println!("Error: invalid duration {}.", older_than);
+                    return Err(error);
+                }
+            },
+            None => None,
+        };
+
+        let mut revisions: Vec<(i32, Revision)> = Vec::new();
+        for revision_number in &revision_numbers {
+            let revision = match Revision::load(commons::revision_path(*revision_number)) {
+                Ok(revision) => revision,
+                Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_REVISION_FAILED)),
+            };
+            revisions.push((*revision_number, revision));
+        }
+
+        let mut kept_numbers = revision_numbers.clone();
+        let mut reasons: HashMap<i32, Vec<&'static str>> = HashMap::new();
+        if !self.policy.is_empty() {
+            let times: Vec<(i32, i64)> = revisions
+                .iter()
+                .map(|(revision_number, revision)| (*revision_number, revision.commited))
+                .collect();
+            reasons = self.policy.kept_revisions(&times);
+            kept_numbers.retain(|revision_number| reasons.contains_key(revision_number));
+        }
+        for (revision_number, revision) in &revisions {
+            if self.revision_numbers.contains(revision_number)
+                || ranges.iter().any(|range| range.contains(revision_number))
+                || older_than.is_some_and(|older_than| revision.commited < older_than)
+            {
+                kept_numbers.retain(|kept_number| kept_number != revision_number);
+            }
+        }
+
+        let model = SizeModel::new(&revisions)?;
+        let size_before = model.size_of(&revision_numbers);
+        if let Some(max_size) = max_size {
+            model.trim(&mut kept_numbers, max_size);
+        }
+        if kept_numbers.len() == revision_numbers.len() {
+            println!("No revisions to forget.");
             return Ok(());
         }
-        let index: usize = removed_count as usize;
-        revision_numbers = revision_numbers.drain(index..).collect();
-        repository.set_revision_numbers(&revision_numbers);
+
+        for revision_number in &revision_numbers {
+            if !kept_numbers.contains(revision_number) {
+                println!("Forgetting: revision {}", revision_number);
+                continue;
+            }
+            match reasons.get(revision_number) {
+                Some(reasons) => println!(
+                    "Keeping: revision {} ({})",
+                    revision_number,
+                    reasons.join(", ")
+                ),
+                None => println!("Keeping: revision {}", revision_number),
+            }
+        }
+        let projected = size_before - model.size_of(&kept_numbers);
+        let actual_before = repository_size()?;
+        repository.set_revision_numbers(&kept_numbers);
         repository.save(&Path::new(".zatsu"))?;
         process_garbage_collection()?;
+        let actual = actual_before.saturating_sub(repository_size()?);
+        println!(
+            "{} projected to be freed, {} actually freed.",
+            commons::formatted_size(projected),
+            commons::formatted_size(actual)
+        );
 
         Ok(())
     }
 }
 
 impl ForgetCommand {
-    pub fn new(revision_count: i32) -> Self {
-        Self { revision_count }
+    pub fn new(
+        policy: KeepPolicy,
+        max_size: Option<&str>,
+        revision_numbers: &[i32],
+        ranges: &[String],
+        older_than: Option<&str>,
+    ) -> Self {
+        Self {
+            policy,
+            max_size: max_size.map(|max_size| max_size.to_string()),
+            revision_numbers: revision_numbers.to_vec(),
+            ranges: ranges.to_vec(),
+            older_than: older_than.map(|older_than| older_than.to_string()),
+        }
+    }
+}
+
+impl SizeModel {
+    fn new(revisions: &[(i32, Revision)]) -> Result<Self, ZatsuError> {
+        let mut revision_sizes: HashMap<i32, u64> = HashMap::new();
+        let mut object_sizes: HashMap<String, u64> = HashMap::new();
+        let mut hashes: HashMap<i32, HashSet<String>> = HashMap::new();
+        for (revision_number, revision) in revisions {
+            revision_sizes.insert(
+                *revision_number,
+                file_size(commons::revision_path(*revision_number)),
+            );
+            let mut revision_hashes: HashSet<String> = HashSet::new();
+            for entry in &revision.entries {
+                if !object_sizes.contains_key(&entry.hash) {
+                    let path = format!(".zatsu/objects/{}/{}", &entry.hash[0..2], entry.hash);
+                    object_sizes.insert(entry.hash.clone(), file_size(path));
+                }
+                revision_hashes.insert(entry.hash.clone());
+            }
+            hashes.insert(*revision_number, revision_hashes);
+        }
+
+        Ok(Self {
+            revision_sizes,
+            object_sizes,
+            hashes,
+        })
+    }
+
+    /// Returns bytes of revision files and objects used by the revisions.
+    fn size_of(&self, revision_numbers: &[i32]) -> u64 {
+        let mut used: HashSet<&String> = HashSet::new();
+        let mut size = 0;
+        for revision_number in revision_numbers {
+            size += self.revision_sizes[revision_number];
+            used.extend(self.hashes[revision_number].iter());
+        }
+
+        size + used
+            .iter()
+            .map(|hash| self.object_sizes[*hash])
+            .sum::<u64>()
+    }
+
+    /// Removes the oldest revisions until the rest fit in max_size.
+    fn trim(&self, revision_numbers: &mut Vec<i32>, max_size: u64) {
+        let mut counts: HashMap<&String, usize> = HashMap::new();
+        for revision_number in revision_numbers.iter() {
+            for hash in &self.hashes[revision_number] {
+                *counts.entry(hash).or_default() += 1;
+            }
+        }
+
+        let mut size = self.size_of(revision_numbers);
+        while size > max_size && !revision_numbers.is_empty() {
+            let revision_number = revision_numbers.remove(0);
+            // Objects only this revision uses are freed with it.
+            let mut freed = self.revision_sizes[&revision_number];
+            for hash in &self.hashes[&revision_number] {
+                let count = counts.get_mut(hash).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    freed += self.object_sizes[hash];
+                }
+            }
+            println!(
+                "Revision {} uniquely uses {}.",
+                revision_number,
+                commons::formatted_size(freed)
+            );
+            size -= freed;
+        }
+    }
+}
+
+fn file_size(path: impl AsRef<Path>) -> u64 {
+    match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Returns bytes of revision files and objects in the repository.
+fn repository_size() -> Result<u64, ZatsuError> {
+    let mut size = 0;
+    for directory_path in [".zatsu/revisions", ".zatsu/objects"] {
+        let read_dir = match fs::read_dir(directory_path) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return Err(ZatsuError::new(error::CODE_READING_DIRECTORY_FAILED)),
+        };
+        for entry in read_dir.flatten() {
+            let read_dir = match fs::read_dir(entry.path()) {
+                Ok(read_dir) => read_dir,
+                Err(_) => continue,
+            };
+            for entry in read_dir.flatten() {
+                size += file_size(entry.path());
+            }
+        }
+    }
+
+    Ok(size)
+}
+
+impl KeepPolicy {
+    pub fn is_empty(&self) -> bool {
+        *self == KeepPolicy::default()
+    }
+
+    /// Returns numbers of revisions kept by any of the policies with the names of
+    /// the policies. Revisions are pairs of numbers and commited times. Keep-last
+    /// keeps the revisions with the largest numbers, and the other policies keep
+    /// the newest revision in each period by commited times.
+    pub fn kept_revisions(&self, revisions: &[(i32, i64)]) -> HashMap<i32, Vec<&'static str>> {
+        let mut sorted_by_number = revisions.to_vec();
+        sorted_by_number.sort_by_key(|(revision_number, _)| Reverse(*revision_number));
+        let mut sorted_by_time = revisions.to_vec();
+        sorted_by_time
+            .sort_by_key(|(revision_number, commited)| Reverse((*commited, *revision_number)));
+
+        let policies: [(&'static str, Option<usize>, &str); 6] = [
+            ("last", self.last, ""),
+            ("hourly", self.hourly, "%Y-%m-%d %H"),
+            ("daily", self.daily, "%Y-%m-%d"),
+            ("weekly", self.weekly, "%G-%V"),
+            ("monthly", self.monthly, "%Y-%m"),
+            ("yearly", self.yearly, "%Y"),
+        ];
+        let mut kept: HashMap<i32, Vec<&'static str>> = HashMap::new();
+        for (name, count, format) in policies {
+            let count = match count {
+                Some(count) => count,
+                None => continue,
+            };
+            let mut kept_count = 0;
+            let mut last_bucket: Option<String> = None;
+            let sorted = match format {
+                "" => &sorted_by_number,
+                _ => &sorted_by_time,
+            };
+            for (revision_number, commited) in sorted {
+                if kept_count >= count {
+                    break;
+                }
+                // Every revision is its own bucket for keep-last.
+                let bucket = match format {
+                    "" => revision_number.to_string(),
+                    format => bucket_of(*commited, format),
+                };
+                if last_bucket.as_ref() == Some(&bucket) {
+                    continue;
+                }
+                kept.entry(*revision_number).or_default().push(name);
+                last_bucket = Some(bucket);
+                kept_count += 1;
+            }
+        }
+
+        kept
+    }
+}
+
+fn bucket_of(commited: i64, format: &str) -> String {
+    match DateTime::from_timestamp_millis(commited) {
+        Some(time) => {
+            let time = time.with_timezone(&Local);
+            // ISO weeks belong to years that may differ from calendar years.
+            if format == "%G-%V" {
+                let week = time.iso_week();
+                return format!("{}-{}", week.year(), week.week());
+            }
+            time.format(format).to_string()
+        }
+        None => String::new(),
     }
 }
 
@@ -165,11 +476,7 @@ fn remove_unused_objects(
     for revision_number in &repository.revision_numbers() {
         println!("Checking: revision {}", revision_number);
 
-        let revision = match Revision::load(format!(
-            ".zatsu/revisions/{:02x}/{}.json",
-            revision_number & 0xFF,
-            revision_number
-        )) {
+        let revision = match Revision::load(commons::revision_path(*revision_number)) {
             Ok(revision) => revision,
             Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
         };
@@ -267,12 +574,22 @@ mod tests {
     use std::env;
     use std::fs;
 
+    use chrono::TimeZone;
+
+    use crate::import_command::ImportCommand;
     use crate::CommitCommand;
     use crate::InitCommand;
 
+    fn keep_last(count: usize) -> KeepPolicy {
+        KeepPolicy {
+            last: Some(count),
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn is_creatable() {
-        let _command = ForgetCommand::new(1);
+        let _command = ForgetCommand::new(keep_last(1), None, &[], &[], None);
     }
 
     #[test]
@@ -282,29 +599,192 @@ mod tests {
         let command = InitCommand::new(1);
         command.execute().unwrap();
         fs::write("a.txt", "Hello, World!").unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = ForgetCommand::new(keep_last(1), None, &[], &[], None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
         command.execute().unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
         command.execute().unwrap();
-        let command = ForgetCommand::new(1);
+        let command = ForgetCommand::new(keep_last(1), None, &[], &[], None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn is_executable_with_policy() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        for i in 0..3 {
+            fs::write("a.txt", format!("Hello, {}!", i)).unwrap();
+            let command = CommitCommand::new("", false, false);
+            command.execute().unwrap();
+        }
+        let policy = KeepPolicy {
+            last: Some(1),
+            daily: Some(1),
+            ..Default::default()
+        };
+        let command = ForgetCommand::new(policy, None, &[], &[], None);
         let result = command.execute();
         assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert_eq!(vec![3], repository.revision_numbers());
+        assert!(!Path::new(&commons::revision_path(1)).exists());
         env::set_current_dir("..").unwrap();
         fs::remove_dir_all("tmp").unwrap();
+    }
 
+    #[test]
+    fn is_executable_with_max_size() {
         fs::create_dir("tmp").unwrap();
         env::set_current_dir("tmp").unwrap();
         let command = InitCommand::new(2);
         command.execute().unwrap();
         fs::write("a.txt", "Hello, World!").unwrap();
-        let command = CommitCommand::new();
+        for i in 0..3 {
+            fs::write("b.txt", format!("{}", i).repeat(1000 * (i + 1))).unwrap();
+            let command = CommitCommand::new("", false, false);
+            command.execute().unwrap();
+        }
+        let command = ForgetCommand::new(KeepPolicy::default(), Some("1GiB"), &[], &[], None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert_eq!(vec![1, 2, 3], repository.revision_numbers());
+
+        let revisions: Vec<(i32, Revision)> = repository
+            .revision_numbers()
+            .iter()
+            .map(|number| {
+                (
+                    *number,
+                    Revision::load(commons::revision_path(*number)).unwrap(),
+                )
+            })
+            .collect();
+        let model = SizeModel::new(&revisions).unwrap();
+        let max_size = model.size_of(&[2, 3]);
+        let max_size = max_size.to_string();
+        let command = ForgetCommand::new(KeepPolicy::default(), Some(&max_size), &[], &[], None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert_eq!(vec![2, 3], repository.revision_numbers());
+        assert_eq!(max_size, repository_size().unwrap().to_string());
+        let command = ForgetCommand::new(KeepPolicy::default(), Some("0"), &[], &[], None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert!(repository.revision_numbers().is_empty());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn is_executable_with_revisions() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
         command.execute().unwrap();
-        let command = CommitCommand::new();
+        fs::create_dir("old").unwrap();
+        fs::write("old/a.txt", "Hello, Old!").unwrap();
+        let command = ImportCommand::new("old", "2000-01-01", "");
         command.execute().unwrap();
-        let command = ForgetCommand::new(1);
+        for i in 0..5 {
+            fs::write("a.txt", format!("Hello, {}!", i)).unwrap();
+            let command = CommitCommand::new("", false, false);
+            command.execute().unwrap();
+        }
+
+        let ranges = vec!["4..5".to_string()];
+        let command = ForgetCommand::new(KeepPolicy::default(), None, &[2], &ranges, Some("90d"));
         let result = command.execute();
         assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert_eq!(vec![3, 6], repository.revision_numbers());
+        let command = ForgetCommand::new(KeepPolicy::default(), None, &[2], &[], None);
+        let result = command.execute();
+        assert!(result.is_err());
+
+        let ranges = vec!["1..".to_string()];
+        let command = ForgetCommand::new(KeepPolicy::default(), None, &[6], &ranges, None);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert!(repository.revision_numbers().is_empty());
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let repository = factory::load(".zatsu").unwrap();
+        assert_eq!(vec![7], repository.revision_numbers());
         env::set_current_dir("..").unwrap();
         fs::remove_dir_all("tmp").unwrap();
     }
+
+    #[test]
+    fn revisions_are_keepable() {
+        let hour = 60 * 60 * 1000;
+        let day = 24 * hour;
+        let base = Local
+            .with_ymd_and_hms(2024, 1, 1, 12, 0, 0)
+            .unwrap()
+            .timestamp_millis();
+        let revisions = vec![
+            (1, base),
+            (2, base + hour),
+            (3, base + day),
+            (4, base + day + hour),
+            (5, base + 40 * day),
+        ];
+        let policy = KeepPolicy {
+            last: Some(1),
+            daily: Some(2),
+            monthly: Some(3),
+            ..Default::default()
+        };
+        let kept = policy.kept_revisions(&revisions);
+        let mut numbers: Vec<&i32> = kept.keys().collect();
+        numbers.sort();
+        assert_eq!(vec![&4, &5], numbers);
+        assert_eq!(vec!["last", "daily", "monthly"], kept[&5]);
+        assert_eq!(vec!["daily", "monthly"], kept[&4]);
+
+        let policy = KeepPolicy {
+            hourly: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(5, policy.kept_revisions(&revisions).len());
+        let policy = KeepPolicy {
+            yearly: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(1, policy.kept_revisions(&revisions).len());
+
+        // An imported revision can be older than revisions before it.
+        let revisions = vec![(1, base), (2, base + day), (3, base - day)];
+        let policy = KeepPolicy {
+            last: Some(1),
+            daily: Some(1),
+            ..Default::default()
+        };
+        let kept = policy.kept_revisions(&revisions);
+        assert_eq!(vec!["last"], kept[&3]);
+        assert_eq!(vec!["daily"], kept[&2]);
+    }
 }
@@ -20,20 +20,36 @@
  * DEALINGS IN THE SOFTWARE.
  */
 
-use flate2::write::ZlibDecoder;
+use clap::ValueEnum;
+use std::env;
 use std::fs;
-use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
 
+use crate::commons;
 use crate::error;
 use crate::repository::factory;
 use crate::Command;
+use crate::Entry;
 use crate::Revision;
 use crate::ZatsuError;
 
+/// How to handle a file that already exists at the output path.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum ConflictPolicy {
+    Overwrite,
+    Skip,
+    Rename,
+    Fail,
+}
+
 pub struct GetCommand {
     revision_number: i32,
     path: String,
+    output: Option<String>,
+    into: Option<String>,
+    exact: bool,
+    conflict: ConflictPolicy,
 }
 
 impl Command for GetCommand {
@@ -45,44 +61,20 @@ impl Command for GetCommand {
                 return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
             }
         };
-        let mut found = false;
-        for a_revision_number in repository.revision_numbers() {
-            if a_revision_number == self.revision_number {
-                found = true;
-            }
-        }
-        if !found {
-            return Err(ZatsuError::new(error::CODE_REVISION_NOT_FOUND));
-        }
-
-        let revision = match Revision::load(format!(
-            ".zatsu/revisions/{:02x}/{}.json",
-            self.revision_number & 0xFF,
-            self.revision_number
-        )) {
-            Ok(revision) => revision,
-            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_REVISION_FAILED)),
-        };
-        let mut hash = "".to_string();
-        let mut file_found = false;
+        let revision = commons::load_revision(&repository, self.revision_number)?;
+        let path = self.normalized_path();
+        let mut file_entry: Option<&Entry> = None;
         let mut directory_found = false;
         for entry in &revision.entries {
-            if entry.path == *self.path {
-                file_found = true;
-                hash = entry.hash.clone();
-            }
-
-            if entry.path.contains("/") {
-                if let Some(index) = entry.path.find(&self.path) {
-                    if index == 0 && self.path.len() <= entry.path.len() - 2 {
-                        directory_found = true;
-                    }
-                }
+            if entry.path == path {
+                file_entry = Some(entry);
+            } else if commons::is_in_path(&entry.path, &path) {
+                directory_found = true;
             }
         }
 
-        if file_found {
-            return self.save_file(&hash);
+        if let Some(entry) = file_entry {
+            return self.save_file(entry);
         }
         if directory_found {
             return self.save_directory(&revision);
@@ -93,120 +85,153 @@ impl Command for GetCommand {
 }
 
 impl GetCommand {
-    pub fn new(revision_number: i32, path: &str) -> Self {
+    pub fn new(
+        revision_number: i32,
+        path: &str,
+        output: Option<&str>,
+        into: Option<&str>,
+        exact: bool,
+        conflict: ConflictPolicy,
+    ) -> Self {
         Self {
             revision_number,
             path: path.to_string(),
+            output: output.map(|output| output.to_string()),
+            into: into.map(|into| into.to_string()),
+            exact,
+            conflict,
         }
     }
 
-    fn save_file(&self, hash: &str) -> Result<(), ZatsuError> {
-        println!("Processing: {}", self.path);
-
-        let directory_name = hash[0..2].to_string();
-        let values = match fs::read(&PathBuf::from(format!(
-            ".zatsu/objects/{}/{}",
-            directory_name, hash
-        ))) {
-            Ok(values) => values,
-            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
-        };
-        let mut decoder = ZlibDecoder::new(Vec::new());
-        match decoder.write_all(&values) {
-            Ok(()) => (),
-            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    fn save_file(&self, entry: &Entry) -> Result<(), ZatsuError> {
+        let file_name = match Path::new(&entry.path).file_name() {
+            Some(file_name) => file_name.to_string_lossy().to_string(),
+            None => "out.dat".to_string(),
         };
-        let decoded = match decoder.finish() {
-            Ok(decoded) => decoded,
-            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+        let output_path = self.output_path(&file_name);
+        self.save_entries(&[(entry, output_path)])
+    }
+
+    fn save_directory(&self, revision: &Revision) -> Result<(), ZatsuError> {
+        let path = self.normalized_path();
+        let mut option = Path::new(&path).file_name().map(|name| name.to_os_string());
+        if path.is_empty() {
+            // The root is named after the current directory.
+            option = match env::current_dir() {
+                Ok(current_dir) => current_dir.file_name().map(|name| name.to_os_string()),
+                Err(_) => None,
+            };
+        }
+        let directory_name = match option {
+            Some(directory_name) => directory_name.to_string_lossy().to_string(),
+            None => "root".to_string(),
         };
-        let split: Vec<_> = self.path.split("/").collect();
-        let mut file_name = "out.dat".to_string();
-        if split.len() >= 1 {
-            let original_file_name = split[split.len() - 1].to_string();
-            let split: Vec<_> = original_file_name.split(".").collect();
-            if split.len() > 1 {
-                file_name = format!("{}-r{}.{}", split[0], self.revision_number, split[1]);
+        let root_path = self.output_path(&directory_name);
+
+        let mut entries: Vec<(&Entry, PathBuf)> = Vec::new();
+        for entry in &revision.entries {
+            if !commons::is_in_path(&entry.path, &path) {
+                continue;
+            }
+            let mut relative_path = entry.path.as_str();
+            if !path.is_empty() {
+                relative_path = &entry.path[(path.len() + 1)..];
             }
+            entries.push((entry, root_path.join(relative_path)));
         }
-        match fs::write(&PathBuf::from(file_name), decoded) {
-            Ok(()) => (),
-            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
-        };
 
-        Ok(())
+        self.save_entries(&entries)
     }
 
-    fn save_directory(&self, revision: &Revision) -> Result<(), ZatsuError> {
-        // Make root directory.
-        let root_path: String;
-        let split: Vec<_> = self.path.split("/").collect();
-        let count = split.len();
-        if count >= 1 {
-            root_path = format!("{}-r{}", split[count - 1], self.revision_number);
-        } else {
-            root_path = format!("{}-r{}", self.path, self.revision_number);
+    /// Returns the path in the form of entries. The root is an empty string.
+    fn normalized_path(&self) -> String {
+        let path = self.path.trim_end_matches('/');
+        let path = path.strip_prefix("./").unwrap_or(path);
+        if path == "." {
+            return String::new();
         }
-        match fs::create_dir(&root_path) {
-            Ok(_) => (),
-            Err(_) => return Err(ZatsuError::new(error::CODE_CREATING_DIRECTORY_FAILED)),
-        };
 
-        let mut hash: String;
-        for entry in &revision.entries {
-            if let Some(_) = entry.path.find(&self.path) {
-                println!("Processing: {}", entry.path);
-
-                hash = entry.hash.clone();
-                let directory_name = hash[0..2].to_string();
-                let values = match fs::read(&PathBuf::from(format!(
-                    ".zatsu/objects/{}/{}",
-                    directory_name, hash
-                ))) {
-                    Ok(values) => values,
-                    Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
-                };
-                let mut decoder = ZlibDecoder::new(Vec::new());
-                match decoder.write_all(&values) {
-                    Ok(()) => (),
-                    Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
-                };
-                let decoded = match decoder.finish() {
-                    Ok(decoded) => decoded,
-                    Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
-                };
+        path.to_string()
+    }
+
+    fn output_path(&self, name: &str) -> PathBuf {
+        if let Some(output) = &self.output {
+            return PathBuf::from(output);
+        }
+
+        let mut name = name.to_string();
+        if !self.exact {
+            name = suffixed_name(&name, &format!("-r{}", self.revision_number));
+        }
+        match &self.into {
+            Some(into) => Path::new(into).join(name),
+            None => PathBuf::from(name),
+        }
+    }
 
-                let split: Vec<_> = entry.path.split("/").collect();
-                let mut file_name = "out.dat".to_string();
-                let count = split.len();
-                if count >= 1 {
-                    file_name = split[count - 1].to_string();
+    fn save_entries(&self, entries: &[(&Entry, PathBuf)]) -> Result<(), ZatsuError> {
+        if self.conflict == ConflictPolicy::Fail {
+            // Check all files first not to leave a partial result.
+            for (_, path) in entries {
+                if fs::symlink_metadata(path).is_ok() {
+                    println!("Error: {} already exists.", path.to_string_lossy());
+                    return Err(ZatsuError::new(error::CODE_FILE_ALREADY_EXISTS));
                 }
+            }
+        }
 
-                // Make sub directries.
-                let mut path = root_path.clone();
-                if count >= 3 {
-                    for i in 0..(count - 2) {
-                        path += &("/".to_string() + &split[i + 1]);
+        for (entry, path) in entries {
+            let mut path = path.clone();
+            if fs::symlink_metadata(&path).is_ok() {
+                match self.conflict {
+                    ConflictPolicy::Skip => {
+                        println!("Skipping: {}", path.to_string_lossy());
+                        continue;
                     }
+                    ConflictPolicy::Rename => path = renamed_path(&path),
+                    _ => (),
                 }
-                match fs::create_dir_all(&path) {
-                    Ok(_) => (),
-                    Err(_) => return Err(ZatsuError::new(error::CODE_CREATING_DIRECTORY_FAILED)),
-                };
+            }
 
-                path += &("/".to_string() + &file_name);
-                match fs::write(path, decoded) {
+            println!("Processing: {}", entry.path);
+            if let Some(parent) = path.parent() {
+                match fs::create_dir_all(parent) {
                     Ok(()) => (),
-                    Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+                    Err(_) => return Err(ZatsuError::new(error::CODE_CREATING_DIRECTORY_FAILED)),
                 };
             }
+            commons::write_entry(entry, &path)?;
         }
 
         Ok(())
     }
 }
 
+/// Inserts the suffix before the extensions, e.g. archive.tar.gz to archive-r3.tar.gz.
+fn suffixed_name(name: &str, suffix: &str) -> String {
+    // A leading dot is a part of the stem, like .bashrc.
+    let index = match name.char_indices().skip(1).find(|(_, c)| *c == '.') {
+        Some((index, _)) => index,
+        None => name.len(),
+    };
+    format!("{}{}{}", &name[..index], suffix, &name[index..])
+}
+
+fn renamed_path(path: &Path) -> PathBuf {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => "out.dat".to_string(),
+    };
+    let mut number = 1;
+    loop {
+        let renamed = path.with_file_name(suffixed_name(&name, &format!("-{}", number)));
+        if fs::symlink_metadata(&renamed).is_err() {
+            return renamed;
+        }
+        number += 1;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,7 +244,7 @@ mod tests {
 
     #[test]
     fn is_creatable() {
-        let _command = GetCommand::new(1, "a.txt");
+        let _command = GetCommand::new(1, "a.txt", None, None, false, ConflictPolicy::Overwrite);
     }
 
     #[test]
@@ -229,9 +254,9 @@ mod tests {
         let command = InitCommand::new(1);
         command.execute().unwrap();
         fs::write("a.txt", "Hello, World!").unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
         command.execute().unwrap();
-        let command = GetCommand::new(1, "a.txt");
+        let command = GetCommand::new(1, "a.txt", None, None, false, ConflictPolicy::Overwrite);
         let result = command.execute();
         assert!(result.is_ok());
         let string = fs::read_to_string("a-r1.txt").unwrap();
@@ -244,9 +269,9 @@ mod tests {
         let command = InitCommand::new(2);
         command.execute().unwrap();
         fs::write("a.txt", "Hello, World!").unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
         command.execute().unwrap();
-        let command = GetCommand::new(1, "a.txt");
+        let command = GetCommand::new(1, "a.txt", None, None, false, ConflictPolicy::Overwrite);
         let result = command.execute();
         assert!(result.is_ok());
         let string = fs::read_to_string("a-r1.txt").unwrap();
@@ -254,4 +279,161 @@ mod tests {
         env::set_current_dir("..").unwrap();
         fs::remove_dir_all("tmp").unwrap();
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn is_executable_with_permission() {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.sh", "#!/bin/sh").unwrap();
+        fs::set_permissions("a.sh", fs::Permissions::from_mode(0o755)).unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = GetCommand::new(1, "a.sh", None, None, false, ConflictPolicy::Overwrite);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let mode = fs::metadata("a-r1.sh").unwrap().permissions().mode();
+        assert_eq!(0o755, mode & 0o777);
+
+        // A read-only file can be overwritten by getting it again.
+        fs::write("b.txt", "Hello, World!").unwrap();
+        fs::set_permissions("b.txt", fs::Permissions::from_mode(0o444)).unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = GetCommand::new(2, "b.txt", None, None, false, ConflictPolicy::Overwrite);
+        command.execute().unwrap();
+        fs::set_permissions("b-r2.txt", fs::Permissions::from_mode(0o444)).unwrap();
+        let command = GetCommand::new(2, "b.txt", None, None, false, ConflictPolicy::Overwrite);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let mode = fs::metadata("b-r2.txt").unwrap().permissions().mode();
+        assert_eq!(0o444, mode & 0o777);
+        assert_eq!("Hello, World!", fs::read_to_string("b-r2.txt").unwrap());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn is_executable_with_symbolic_link() {
+        use std::os::unix::fs::symlink;
+
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::create_dir("dir").unwrap();
+        fs::write("dir/a.txt", "Hello, World!").unwrap();
+        symlink("a.txt", "dir/b.txt").unwrap();
+        symlink("missing.txt", "dir/c.txt").unwrap();
+        symlink("dir", "link").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let revision = Revision::load(".zatsu/revisions/01/1.json").unwrap();
+        assert_eq!(4, revision.entries.len());
+        let command = GetCommand::new(1, "dir", None, None, false, ConflictPolicy::Overwrite);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let target = fs::read_link("dir-r1/b.txt").unwrap();
+        assert_eq!("a.txt", target.to_string_lossy());
+        let target = fs::read_link("dir-r1/c.txt").unwrap();
+        assert_eq!("missing.txt", target.to_string_lossy());
+        let string = fs::read_to_string("dir-r1/b.txt").unwrap();
+        assert_eq!("Hello, World!", string);
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn is_executable_with_output_options() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::create_dir_all("dir/sub").unwrap();
+        fs::write("archive.tar.gz", "archive").unwrap();
+        fs::write("Makefile", "all:").unwrap();
+        fs::write("dir/sub/a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+
+        let command = GetCommand::new(1, "archive.tar.gz", None, None, false, ConflictPolicy::Fail);
+        assert!(command.execute().is_ok());
+        assert!(Path::new("archive-r1.tar.gz").exists());
+        let command = GetCommand::new(1, "Makefile", None, None, false, ConflictPolicy::Fail);
+        assert!(command.execute().is_ok());
+        assert!(Path::new("Makefile-r1").exists());
+        let command = GetCommand::new(1, "Makefile", None, None, false, ConflictPolicy::Fail);
+        assert!(command.execute().is_err());
+        let command = GetCommand::new(1, "Makefile", None, None, false, ConflictPolicy::Rename);
+        assert!(command.execute().is_ok());
+        assert!(Path::new("Makefile-r1-1").exists());
+
+        let command = GetCommand::new(1, "dir/sub", None, Some("out"), true, ConflictPolicy::Fail);
+        assert!(command.execute().is_ok());
+        assert_eq!(
+            "Hello, World!",
+            fs::read_to_string("out/sub/a.txt").unwrap()
+        );
+        fs::write("out/sub/a.txt", "Changed").unwrap();
+        let command = GetCommand::new(1, "dir/sub", None, Some("out"), true, ConflictPolicy::Skip);
+        assert!(command.execute().is_ok());
+        assert_eq!("Changed", fs::read_to_string("out/sub/a.txt").unwrap());
+
+        let command = GetCommand::new(
+            1,
+            "dir/sub/a.txt",
+            Some("b.txt"),
+            None,
+            false,
+            ConflictPolicy::Overwrite,
+        );
+        assert!(command.execute().is_ok());
+        assert_eq!("Hello, World!", fs::read_to_string("b.txt").unwrap());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn is_executable_with_root() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::create_dir("src").unwrap();
+        fs::create_dir("srcfoo").unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        fs::write("src/b.rs", "").unwrap();
+        fs::write("srcfoo/c.rs", "").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+
+        let command = GetCommand::new(1, "src", None, None, false, ConflictPolicy::Fail);
+        assert!(command.execute().is_ok());
+        assert!(Path::new("src-r1/b.rs").exists());
+        assert!(!Path::new("src-r1/c.rs").exists());
+
+        for path in [".", "/"] {
+            let command = GetCommand::new(1, path, Some("out"), None, false, ConflictPolicy::Fail);
+            assert!(command.execute().is_ok());
+            assert!(Path::new("out/a.txt").exists());
+            assert!(Path::new("out/src/b.rs").exists());
+            assert!(Path::new("out/srcfoo/c.rs").exists());
+            fs::remove_dir_all("out").unwrap();
+        }
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn name_is_suffixable() {
+        assert_eq!("a-r1.txt", suffixed_name("a.txt", "-r1"));
+        assert_eq!("archive-r3.tar.gz", suffixed_name("archive.tar.gz", "-r3"));
+        assert_eq!("Makefile-r3", suffixed_name("Makefile", "-r3"));
+        assert_eq!(".bashrc-r3", suffixed_name(".bashrc", "-r3"));
+    }
 }
@@ -0,0 +1,221 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::commons;
+use crate::diff_command;
+use crate::entry::EntryKind;
+use crate::error;
+use crate::repository::factory;
+use crate::Command;
+use crate::Repository;
+use crate::Revision;
+use crate::ZatsuError;
+
+pub struct GrepCommand {
+    pattern: String,
+    range: Option<String>,
+    paths: Vec<String>,
+}
+
+/// A line that matches the pattern.
+pub struct GrepMatch {
+    pub revision_number: i32,
+    pub path: String,
+    pub line_number: usize,
+    pub text: String,
+}
+
+impl Command for GrepCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+
+        for found in self.matches(&repository)? {
+            println!(
+                "{}:{}:{}:{}",
+                found.revision_number, found.path, found.line_number, found.text
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl GrepCommand {
+    pub fn new(pattern: &str, range: Option<&str>, paths: &[String]) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            range: range.map(|range| range.to_string()),
+            paths: paths.to_vec(),
+        }
+    }
+
+    /// Returns matched lines in order of revisions and paths.
+    pub fn matches(&self, repository: &Box<dyn Repository>) -> Result<Vec<GrepMatch>, ZatsuError> {
+        let regex = match Regex::new(&self.pattern) {
+            Ok(regex) => regex,
+            Err(error) => {
+                println!("Error: invalid pattern. {}", error);
+                return Err(ZatsuError::new(error::CODE_PARSING_PATTERN_FAILED));
+            }
+        };
+        let range = match &self.range {
+            Some(range) => match commons::parse_range(range) {
+                Ok(range) => range,
+                Err(error) => {
+                    println!("Error: invalid revision range {}.", range);
+                    return Err(error);
+                }
+            },
+            None => i32::MIN..=i32::MAX,
+        };
+
+        // Objects are shared by revisions, so matches are cached by hashes.
+        let mut cache: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+        let mut matches: Vec<GrepMatch> = Vec::new();
+        for revision_number in repository.revision_numbers() {
+            if !range.contains(&revision_number) {
+                continue;
+            }
+            let revision = match Revision::load(commons::revision_path(revision_number)) {
+                Ok(revision) => revision,
+                Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_REVISION_FAILED)),
+            };
+            let mut entries = revision.entries;
+            entries.sort_by(|a, b| a.path.cmp(&b.path));
+            for entry in entries {
+                if entry.kind != EntryKind::File
+                    || (!self.paths.is_empty()
+                        && !self
+                            .paths
+                            .iter()
+                            .any(|path| commons::is_in_path(&entry.path, path)))
+                {
+                    continue;
+                }
+
+                if !cache.contains_key(&entry.hash) {
+                    let values = commons::load_object(&entry.hash)?;
+                    cache.insert(entry.hash.clone(), matched_lines(&regex, &values));
+                }
+                for (line_number, text) in &cache[&entry.hash] {
+                    matches.push(GrepMatch {
+                        revision_number,
+                        path: entry.path.clone(),
+                        line_number: *line_number,
+                        text: text.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+}
+
+/// Returns line numbers and texts of matched lines. Binary files never match.
+fn matched_lines(regex: &Regex, values: &[u8]) -> Vec<(usize, String)> {
+    if diff_command::is_binary(values) {
+        return Vec::new();
+    }
+
+    String::from_utf8_lossy(values)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(i, line)| (i + 1, line.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = GrepCommand::new("Hello", None, &[]);
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::create_dir("dir").unwrap();
+        fs::write("a.txt", "Hello, World!\nGoodbye!\n").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("dir/b.txt", "Hello, Zatsu!\n").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+
+        let command = GrepCommand::new("^Hello, [WZ]", None, &[]);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        let formatted: Vec<String> = command
+            .matches(&repository)
+            .unwrap()
+            .iter()
+            .map(|found| {
+                format!(
+                    "{}:{}:{}:{}",
+                    found.revision_number, found.path, found.line_number, found.text
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                "1:a.txt:1:Hello, World!",
+                "2:a.txt:1:Hello, World!",
+                "2:dir/b.txt:1:Hello, Zatsu!"
+            ],
+            formatted
+        );
+
+        let paths = vec!["dir".to_string()];
+        let command = GrepCommand::new("Hello", Some("2"), &paths);
+        assert_eq!(1, command.matches(&repository).unwrap().len());
+        let command = GrepCommand::new("Hello", Some("1..1"), &[]);
+        assert_eq!(1, command.matches(&repository).unwrap().len());
+        let command = GrepCommand::new("(", None, &[]);
+        let result = command.execute();
+        assert!(result.is_err());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+}
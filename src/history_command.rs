@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use crate::change;
+use crate::command::OutputFormat;
+use crate::commons;
+use crate::error;
+use crate::log_command;
+use crate::log_command::LogRecord;
+use crate::repository::factory;
+use crate::Command;
+use crate::Entry;
+use crate::Repository;
+use crate::Revision;
+use crate::ZatsuError;
+
+pub struct HistoryCommand {
+    path: String,
+    follow: bool,
+    format: OutputFormat,
+}
+
+impl Command for HistoryCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+
+        let records = self.records(&repository)?;
+        if records.is_empty() {
+            println!("Error: {} not found in any revision.", self.path);
+            return Err(ZatsuError::new(error::CODE_FILE_NOT_FOUND));
+        }
+        for record in records {
+            log_command::print_record(&record, false, self.format)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl HistoryCommand {
+    pub fn new(path: &str, follow: bool, format: OutputFormat) -> Self {
+        Self {
+            path: path.to_string(),
+            follow,
+            format,
+        }
+    }
+
+    /// Returns records of revisions that changed the file, newest first. Each record
+    /// has only the change of the file.
+    pub fn records(&self, repository: &Box<dyn Repository>) -> Result<Vec<LogRecord>, ZatsuError> {
+        let revision_numbers = repository.revision_numbers();
+        let mut path = self
+            .path
+            .strip_prefix("./")
+            .unwrap_or(&self.path)
+            .to_string();
+        let mut records: Vec<LogRecord> = Vec::new();
+
+        // Walk revisions backward, so that renames are followed to older paths.
+        let mut revision: Option<Revision> = None;
+        for i in (0..revision_numbers.len()).rev() {
+            let current = match revision.take() {
+                Some(revision) => revision,
+                None => load_revision(revision_numbers[i])?,
+            };
+            let previous = match i {
+                0 => None,
+                _ => Some(load_revision(revision_numbers[i - 1])?),
+            };
+            let previous_entries: &[Entry] = match &previous {
+                Some(previous) => &previous.entries,
+                None => &[],
+            };
+
+            let mut changes = change::compare_entries(&current.entries, previous_entries);
+            if self.follow {
+                changes = change::detect_renames(changes);
+            }
+            if let Some(change) = changes.into_iter().find(|change| change.path == path) {
+                if let Some(old_path) = &change.old_path {
+                    path = old_path.clone();
+                }
+                records.push(LogRecord {
+                    number: revision_numbers[i],
+                    commited: current.commited,
+                    description: current.description,
+                    changes: vec![change],
+                });
+            }
+            revision = previous;
+        }
+
+        Ok(records)
+    }
+}
+
+fn load_revision(revision_number: i32) -> Result<Revision, ZatsuError> {
+    match Revision::load(commons::revision_path(revision_number)) {
+        Ok(revision) => Ok(revision),
+        Err(_) => Err(ZatsuError::new(error::CODE_LOADING_REVISION_FAILED)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = HistoryCommand::new("a.txt", false, OutputFormat::Text);
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, Zatsu!").unwrap();
+        fs::write("c.txt", "").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("c.txt", "Hello, Zatsu!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::rename("a.txt", "b.txt").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+
+        let command = HistoryCommand::new("./b.txt", false, OutputFormat::Text);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        let records = command.records(&repository).unwrap();
+        let numbers: Vec<i32> = records.iter().map(|record| record.number).collect();
+        assert_eq!(vec![4], numbers);
+
+        let command = HistoryCommand::new("b.txt", true, OutputFormat::Json);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let records = command.records(&repository).unwrap();
+        let formatted: Vec<String> = records
+            .iter()
+            .map(|record| format!("{} {}", record.number, record.changes[0]))
+            .collect();
+        assert_eq!(
+            vec!["4 R a.txt -> b.txt", "2 M a.txt", "1 A a.txt"],
+            formatted
+        );
+
+        let command = HistoryCommand::new("d.txt", false, OutputFormat::Text);
+        let result = command.execute();
+        assert!(result.is_err());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+}
@@ -0,0 +1,397 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use flate2::read::GzDecoder;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+
+use crate::change;
+use crate::commons;
+use crate::entry::EntryKind;
+use crate::error;
+use crate::repository::factory;
+use crate::Command;
+use crate::Entry;
+use crate::FilePathProducer;
+use crate::Repository;
+use crate::Revision;
+use crate::ZatsuError;
+
+pub struct ImportCommand {
+    path: String,
+    time: String,
+    description: String,
+}
+
+impl Command for ImportCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let mut repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+        let commited = match commons::parse_time(&self.time) {
+            Ok(commited) => commited,
+            Err(error) => {
+                println!("Error: invalid time {}.", self.time);
+                return Err(error);
+            }
+        };
+
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                println!("Error: {} not found.", self.path);
+                return Err(ZatsuError::new(error::CODE_FILE_NOT_FOUND));
+            }
+        };
+        let entries = if metadata.is_dir() {
+            import_directory(&self.path, &repository)?
+        } else {
+            import_archive(&self.path, &repository)?
+        };
+
+        // Like commit, nothing is saved if files are the same as the latest revision.
+        let latest_revision = repository.latest_revision();
+        if latest_revision > 0 {
+            let latest = Revision::load(commons::revision_path(latest_revision))?;
+            let changes = change::compare_entries(&entries, &latest.entries);
+            if changes.is_empty() {
+                println!();
+                println!("Nothing to import.");
+                return Ok(());
+            }
+        }
+
+        let mut description = self.description.clone();
+        if description.is_empty() {
+            description = format!("Imported from {}", self.path);
+        }
+        let revision = Revision {
+            commited,
+            entries,
+            description,
+        };
+        let revision_number = commons::save_revision(&mut repository, &revision)?;
+
+        println!();
+        println!(
+            "Imported {} file(s) as revision {}.",
+            revision.entries.len(),
+            revision_number
+        );
+        println!(
+            "There are {} revision(s).",
+            repository.revision_numbers().len()
+        );
+
+        Ok(())
+    }
+}
+
+impl ImportCommand {
+    pub fn new(path: &str, time: &str, description: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            time: time.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+fn import_directory(
+    path: &str,
+    repository: &Box<dyn Repository>,
+) -> Result<Vec<Entry>, ZatsuError> {
+    let directory_path = path.trim_end_matches('/');
+    let mut producer = FilePathProducer::new(directory_path.to_string());
+    let mut entries: Vec<Entry> = Vec::new();
+    loop {
+        let relative_path = match producer.next() {
+            Ok(relative_path) => relative_path,
+            Err(error) => {
+                if error.code == error::CODE_PRODUCING_FINISHED {
+                    break;
+                }
+                return Err(error);
+            }
+        };
+        println!("Processing: {}", relative_path);
+        let file_path = Path::new(directory_path).join(&relative_path);
+        let metadata = match fs::symlink_metadata(&file_path) {
+            Ok(metadata) => metadata,
+            Err(_) => return Err(ZatsuError::new(error::CODE_READING_META_DATA_FAILED)),
+        };
+        let mut kind = EntryKind::File;
+        let mut permission = commons::file_permission(&metadata);
+        if metadata.is_symlink() {
+            kind = EntryKind::SymbolicLink;
+            permission = 0o777;
+        }
+        let values = commons::load_file(&file_path)?;
+        let hash = repository.object_hash(&values);
+        commons::save_object(&values, &hash)?;
+        entries.push(Entry {
+            path: relative_path,
+            hash,
+            permission,
+            kind,
+        });
+    }
+
+    Ok(entries)
+}
+
+fn import_archive(path: &str, repository: &Box<dyn Repository>) -> Result<Vec<Entry>, ZatsuError> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    };
+    let mut reader = BufReader::new(file);
+    let compressed = match reader.fill_buf() {
+        Ok(values) => values.starts_with(&[0x1f, 0x8b]),
+        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    };
+    if compressed {
+        read_tar(GzDecoder::new(reader), repository)
+    } else {
+        read_tar(reader, repository)
+    }
+}
+
+fn read_tar<R: Read>(
+    reader: R,
+    repository: &Box<dyn Repository>,
+) -> Result<Vec<Entry>, ZatsuError> {
+    let mut archive = tar::Archive::new(reader);
+    let tar_entries = match archive.entries() {
+        Ok(tar_entries) => tar_entries,
+        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+    };
+
+    // Later entries replace earlier ones with the same path, as extracting does.
+    let mut entries: BTreeMap<String, Entry> = BTreeMap::new();
+    for result in tar_entries {
+        let mut tar_entry = match result {
+            Ok(tar_entry) => tar_entry,
+            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+        };
+        let entry_path = match tar_entry.path() {
+            Ok(entry_path) => entry_path.to_path_buf(),
+            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+        };
+        let path = match normalized_path(&entry_path) {
+            Some(path) => path,
+            None => {
+                println!("Warning: {} is skipped.", entry_path.to_string_lossy());
+                continue;
+            }
+        };
+
+        let entry_type = tar_entry.header().entry_type();
+        if entry_type.is_hard_link() {
+            // A hard link shares the contents of an earlier entry in the archive.
+            let target = match tar_entry.link_name() {
+                Ok(Some(target)) => normalized_path(&target),
+                _ => None,
+            };
+            let target_entry = match target.and_then(|target| entries.get(&target)) {
+                Some(target_entry) => target_entry,
+                None => {
+                    println!("Warning: {} is skipped.", path);
+                    continue;
+                }
+            };
+            println!("Processing: {}", path);
+            let entry = Entry {
+                path: path.clone(),
+                hash: target_entry.hash.clone(),
+                permission: target_entry.permission,
+                kind: target_entry.kind,
+            };
+            entries.insert(path, entry);
+            continue;
+        }
+
+        let mut values: Vec<u8> = Vec::new();
+        let kind;
+        let permission;
+        if entry_type.is_file() {
+            match tar_entry.read_to_end(&mut values) {
+                Ok(_) => (),
+                Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+            };
+            kind = EntryKind::File;
+            permission = match tar_entry.header().mode() {
+                Ok(mode) => (mode & 0o777) as i32,
+                Err(_) => 0o644,
+            };
+        } else if entry_type.is_symlink() {
+            let target = match tar_entry.link_name() {
+                Ok(Some(target)) => target.to_string_lossy().to_string(),
+                _ => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+            };
+            values = target.into_bytes();
+            kind = EntryKind::SymbolicLink;
+            permission = 0o777;
+        } else {
+            if !entry_type.is_dir() {
+                println!("Warning: {} is skipped.", path);
+            }
+            continue;
+        }
+
+        println!("Processing: {}", path);
+        let hash = repository.object_hash(&values);
+        commons::save_object(&values, &hash)?;
+        entries.insert(
+            path.clone(),
+            Entry {
+                path,
+                hash,
+                permission,
+                kind,
+            },
+        );
+    }
+
+    Ok(entries.into_values().collect())
+}
+
+/// Returns a relative path with "/" separators, or None if the path points
+/// outside of the archive or into a repository directory.
+fn normalized_path(path: &Path) -> Option<String> {
+    let mut names: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => names.push(name.to_string_lossy().to_string()),
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+    let first = names.first()?;
+    if first == ".zatsu" || first == ".jj" || first == ".git" {
+        return None;
+    }
+
+    Some(names.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::env;
+
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = ImportCommand::new("backup.tar", "2024-01-01", "");
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Current").unwrap();
+        fs::create_dir_all("backup/dir").unwrap();
+        fs::write("backup/a.txt", "Hello, World!").unwrap();
+        fs::write("backup/dir/b.txt", "Hello, Zatsu!").unwrap();
+
+        let command = ImportCommand::new("backup/", "2024-01-02T03:04:05Z", "");
+        let result = command.execute();
+        assert!(result.is_ok());
+        let revision = Revision::load(".zatsu/revisions/01/1.json").unwrap();
+        assert_eq!(1_704_164_645_000, revision.commited);
+        assert_eq!("Imported from backup/", revision.description);
+        let mut paths: Vec<&str> = revision
+            .entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect();
+        paths.sort();
+        assert_eq!(vec!["a.txt", "dir/b.txt"], paths);
+        assert_eq!("Current", fs::read_to_string("a.txt").unwrap());
+        let command = ImportCommand::new("backup/", "2024-01-03", "");
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        assert_eq!(vec![1], repository.revision_numbers());
+
+        let file = fs::File::create("backup.tar.gz").unwrap();
+        let encoder = GzEncoder::new(file, Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(13);
+        header.set_mode(0o755);
+        builder
+            .append_data(&mut header, "./dir/c.sh", "Hello, Tar!\n\n".as_bytes())
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "dir/d.sh", "./dir/c.sh")
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let command = ImportCommand::new("backup.tar.gz", "1704164700", "Backup");
+        let result = command.execute();
+        assert!(result.is_ok());
+        let revision = Revision::load(".zatsu/revisions/02/2.json").unwrap();
+        assert_eq!(1_704_164_700_000, revision.commited);
+        assert_eq!("Backup", revision.description);
+        assert_eq!(2, revision.entries.len());
+        assert_eq!("dir/c.sh", revision.entries[0].path);
+        assert_eq!(0o755, revision.entries[0].permission);
+        let values = commons::load_object(&revision.entries[0].hash).unwrap();
+        assert_eq!("Hello, Tar!\n\n".as_bytes(), values);
+        assert_eq!("dir/d.sh", revision.entries[1].path);
+        assert_eq!(0o755, revision.entries[1].permission);
+        assert_eq!(revision.entries[0].hash, revision.entries[1].hash);
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn path_is_normalizable() {
+        assert_eq!(
+            Some("dir/a.txt".to_string()),
+            normalized_path(Path::new("./dir/a.txt"))
+        );
+        assert_eq!(None, normalized_path(Path::new("../a.txt")));
+        assert_eq!(None, normalized_path(Path::new("/etc/passwd")));
+        assert_eq!(None, normalized_path(Path::new(".zatsu/repository.json")));
+    }
+}
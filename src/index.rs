@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::error;
+use crate::error::ZatsuError;
+
+/// Stat data of a file at the time it was hashed.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct IndexEntry {
+    pub size: u64,
+    pub modified: i64,
+    pub inode: u64,
+    pub hash: String,
+}
+
+/// Cache of file hashes keyed by path, stored in .zatsu/index.json.
+#[derive(Serialize, Deserialize)]
+pub struct Index {
+    pub version: i32,
+    pub entries: HashMap<String, IndexEntry>,
+}
+
+impl Index {
+    pub fn new(version: i32) -> Self {
+        Self {
+            version,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Index, ZatsuError> {
+        let serialized = match fs::read_to_string(path) {
+            Ok(serialized) => serialized,
+            Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+        };
+        let index = match serde_json::from_str(&serialized) {
+            Ok(index) => index,
+            Err(_) => return Err(ZatsuError::new(error::CODE_DESERIALIZATION_FAILED)),
+        };
+
+        Ok(index)
+    }
+
+    /// Loads the index, or returns an empty one if it is missing or was made for another version.
+    pub fn load_or_new(path: impl AsRef<Path>, version: i32) -> Index {
+        match Index::load(path) {
+            Ok(index) if index.version == version => index,
+            _ => Index::new(version),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ZatsuError> {
+        let serialized = match serde_json::to_string(self) {
+            Ok(serialized) => serialized,
+            Err(_) => return Err(ZatsuError::new(error::CODE_SERIALIZATION_FAILED)),
+        };
+        match fs::write(path, serialized) {
+            Ok(()) => (),
+            Err(_) => return Err(ZatsuError::new(error::CODE_SAVING_FILE_FAILED)),
+        };
+
+        Ok(())
+    }
+
+    /// Returns the stored hash if the stat data of the file is unchanged.
+    pub fn find_hash(&self, path: &str, metadata: &fs::Metadata) -> Option<String> {
+        let entry = self.entries.get(path)?;
+        if *entry == IndexEntry::new(metadata, &entry.hash) {
+            return Some(entry.hash.clone());
+        }
+
+        None
+    }
+
+    pub fn update(&mut self, path: &str, metadata: &fs::Metadata, hash: &str) {
+        // A file modified within the timestamp granularity could change again without
+        // changing its modified time, so it is not cached until it gets older.
+        let entry = IndexEntry::new(metadata, hash);
+        let now = nanoseconds(SystemTime::now());
+        let threshold = Duration::from_secs(2).as_nanos() as i64;
+        if entry.modified < now - threshold {
+            self.entries.insert(path.to_string(), entry);
+        }
+    }
+}
+
+impl IndexEntry {
+    fn new(metadata: &fs::Metadata, hash: &str) -> Self {
+        let modified = match metadata.modified() {
+            Ok(modified) => nanoseconds(modified),
+            Err(_) => 0,
+        };
+
+        Self {
+            size: metadata.len(),
+            modified,
+            inode: inode(metadata),
+            hash: hash.to_string(),
+        }
+    }
+}
+
+fn nanoseconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_nanos() as i64,
+        Err(_) => 0,
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs::File;
+
+    #[test]
+    fn is_savable_and_loadable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let mut index = Index::new(2);
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let file = File::options().write(true).open("a.txt").unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(1000))
+            .unwrap();
+        let metadata = fs::metadata("a.txt").unwrap();
+        index.update("a.txt", &metadata, "12345678");
+        let result = index.save("index.json");
+        assert!(result.is_ok());
+        let index = Index::load("index.json").unwrap();
+        assert_eq!(
+            Some("12345678".to_string()),
+            index.find_hash("a.txt", &metadata)
+        );
+        let index = Index::load_or_new("index.json", 1);
+        assert_eq!(None, index.find_hash("a.txt", &metadata));
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn recently_modified_file_is_not_cached() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let mut index = Index::new(2);
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let metadata = fs::metadata("a.txt").unwrap();
+        index.update("a.txt", &metadata, "12345678");
+        assert_eq!(None, index.find_hash("a.txt", &metadata));
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+}
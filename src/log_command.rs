@@ -20,19 +20,39 @@
  * DEALINGS IN THE SOFTWARE.
  */
 
-use chrono::DateTime;
-use chrono::Local;
-use chrono::Utc;
-use std::collections::HashMap;
+use serde_derive::Serialize;
 
+use crate::change;
+use crate::change::Change;
+use crate::change::ChangeKind;
+use crate::command::OutputFormat;
+use crate::commons;
 use crate::error;
 use crate::repository::factory;
 use crate::Command;
 use crate::Entry;
+use crate::Repository;
 use crate::Revision;
 use crate::ZatsuError;
 
-pub struct LogCommand {}
+/// A revision shown in logs with its changes from the previous revision.
+#[derive(Serialize)]
+pub struct LogRecord {
+    pub number: i32,
+    pub commited: i64,
+    pub description: String,
+    pub changes: Vec<Change>,
+}
+
+pub struct LogCommand {
+    limit: Option<usize>,
+    since: Option<String>,
+    until: Option<String>,
+    paths: Vec<String>,
+    stat: bool,
+    similarity: Option<u8>,
+    format: OutputFormat,
+}
 
 impl Command for LogCommand {
     fn execute(&self) -> Result<(), ZatsuError> {
@@ -44,151 +64,160 @@ impl Command for LogCommand {
             }
         };
 
-        let utc_offset = Local::now().offset().local_minus_utc() as i64;
-        let count = repository.revision_numbers().len();
+        let records = self.records(&repository)?;
+        for record in records {
+            print_record(&record, self.stat, self.format)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl LogCommand {
+    pub fn new(
+        limit: Option<usize>,
+        since: Option<&str>,
+        until: Option<&str>,
+        paths: &[String],
+        stat: bool,
+        similarity: Option<u8>,
+        format: OutputFormat,
+    ) -> Self {
+        Self {
+            limit,
+            since: since.map(|since| since.to_string()),
+            until: until.map(|until| until.to_string()),
+            paths: paths.to_vec(),
+            stat,
+            similarity,
+            format,
+        }
+    }
+
+    /// Returns filtered records, newest first.
+    pub fn records(&self, repository: &Box<dyn Repository>) -> Result<Vec<LogRecord>, ZatsuError> {
+        let since = parse_option_time(&self.since)?;
+        let until = parse_option_time(&self.until)?;
+
+        let revision_numbers = repository.revision_numbers();
+        let count = revision_numbers.len();
+        let mut records: Vec<LogRecord> = Vec::new();
         for i in (0..count).rev() {
-            let revision_number = repository.revision_numbers()[i];
-            let revision = match Revision::load(format!(
-                ".zatsu/revisions/{:02x}/{}.json",
-                revision_number & 0xFF,
-                revision_number
-            )) {
+            if let Some(limit) = self.limit {
+                if records.len() >= limit {
+                    break;
+                }
+            }
+
+            let revision_number = revision_numbers[i];
+            let revision = match Revision::load(commons::revision_path(revision_number)) {
                 Ok(revision) => revision,
                 Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
             };
+            if since.is_some_and(|since| revision.commited < since)
+                || until.is_some_and(|until| revision.commited > until)
+            {
+                continue;
+            }
+
             let entries = revision.entries;
             let mut previous_entries: Vec<Entry> = Vec::new();
             if i > 0 {
-                let previous_revision_number = repository.revision_numbers()[i - 1];
-                let previous_revision = match Revision::load(format!(
-                    ".zatsu/revisions/{:02x}/{}.json",
-                    previous_revision_number & 0xFF,
-                    previous_revision_number
-                )) {
-                    Ok(revision) => revision,
-                    Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
-                };
+                let previous_revision_number = revision_numbers[i - 1];
+                let previous_revision =
+                    match Revision::load(commons::revision_path(previous_revision_number)) {
+                        Ok(revision) => revision,
+                        Err(_) => return Err(ZatsuError::new(error::CODE_LOADING_FILE_FAILED)),
+                    };
                 previous_entries = previous_revision.entries;
             }
 
-            let divided = divided_entries(&entries);
-            let previous_divided = divided_entries(&previous_entries);
-
-            let milliseconds = revision.commited + utc_offset * 1000;
-            let commited = match DateTime::from_timestamp_millis(milliseconds) {
-                Some(commited) => commited,
-                None => Utc::now(),
-            };
-            println!(
-                "Revision {}, commited at {}",
-                revision_number,
-                commited.format("%Y/%m/%d %H:%M")
-            );
-
-            let mut changes: Vec<String> = Vec::new();
-
-            let keys = divided.keys();
-            for key in keys {
-                if !previous_divided.contains_key(key) {
-                    // All entries are appended.
-                    let entries = &divided[&key];
-                    for entry in entries {
-                        changes.push(format!("A {}", entry.path));
-                    }
-                } else {
-                    // Compare entries and add chaned.
-                    let entries = &divided[&key];
-                    let previous_entries = &previous_divided[&key];
-                    update_changes(&mut changes, &entries, &previous_entries);
-                }
+            let changes = change::compare_entries(&entries, &previous_entries);
+            let mut changes = change::detect_renames(changes);
+            if let Some(similarity) = self.similarity {
+                changes =
+                    change::detect_similar_renames(changes, similarity as f32 / 100.0, |entry| {
+                        commons::load_object(&entry.hash)
+                    })?;
             }
-            let keys = previous_divided.keys();
-            for key in keys {
-                if !divided.contains_key(key) {
-                    // All entries are deleted.
-                    let entries = &previous_divided[&key];
-                    for entry in entries {
-                        changes.push(format!("D {}", entry.path));
-                    }
+            if !self.paths.is_empty() {
+                changes.retain(|change| change.is_in_paths(&self.paths));
+                if changes.is_empty() {
+                    continue;
                 }
             }
 
-            for change in changes {
-                println!("{}", change);
-            }
-            println!("");
+            records.push(LogRecord {
+                number: revision_number,
+                commited: revision.commited,
+                description: revision.description,
+                changes,
+            });
         }
 
-        Ok(())
+        Ok(records)
     }
 }
 
-impl LogCommand {
-    pub fn new() -> Self {
-        Self {}
+/// Prints a record in the format. Only counts of changes are printed if stat is true.
+pub fn print_record(
+    record: &LogRecord,
+    stat: bool,
+    format: OutputFormat,
+) -> Result<(), ZatsuError> {
+    if format == OutputFormat::Json {
+        let serialized = match serde_json::to_string(record) {
+            Ok(serialized) => serialized,
+            Err(_) => return Err(ZatsuError::new(error::CODE_SERIALIZATION_FAILED)),
+        };
+        println!("{}", serialized);
+        return Ok(());
     }
-}
 
-fn find_hash(entries: &Vec<Entry>, path: &String) -> Option<String> {
-    for entry in entries {
-        if entry.path == *path {
-            return Some(entry.hash.clone());
-        }
+    println!(
+        "Revision {}, commited at {}",
+        record.number,
+        commons::formatted_time(record.commited)
+    );
+    for line in record.description.lines() {
+        println!("    {}", line);
     }
 
-    None
-}
-
-fn divided_entries(entries: &Vec<Entry>) -> HashMap<char, Vec<Entry>> {
-    let mut result: HashMap<char, Vec<Entry>> = HashMap::new();
-
-    for entry in entries {
-        let key: char;
-        if entry.path.len() > 0 {
-            key = entry.path.chars().nth(0).unwrap();
-        } else {
-            key = char::from_u32(0).unwrap();
-        }
-
-        if !result.contains_key(&key) {
-            result.insert(key, Vec::new());
+    if stat {
+        let count_of = |kind: ChangeKind| {
+            record
+                .changes
+                .iter()
+                .filter(|change| change.kind == kind)
+                .count()
+        };
+        println!(
+            "{} added, {} modified, {} deleted, {} renamed",
+            count_of(ChangeKind::Added),
+            count_of(ChangeKind::Modified),
+            count_of(ChangeKind::Deleted),
+            count_of(ChangeKind::Renamed)
+        );
+    } else {
+        for change in &record.changes {
+            println!("{}", change);
         }
-        let entries = result.get_mut(&key).unwrap();
-        entries.push(entry.clone());
     }
+    println!();
 
-    result
+    Ok(())
 }
 
-fn update_changes(changes: &mut Vec<String>, entries: &Vec<Entry>, previous_entries: &Vec<Entry>) {
-    for entry in entries {
-        let mut found = false;
-        let previous_hash = match find_hash(&previous_entries, &entry.path) {
-            Some(hash) => {
-                found = true;
-                hash
-            }
-            None => String::new(),
-        };
-        if found {
-            if previous_hash != entry.hash {
-                changes.push(format!("M {}", entry.path));
-            }
-        } else {
-            changes.push(format!("A {}", entry.path));
-        }
-    }
-    for entry in previous_entries {
-        let mut found = false;
-        match find_hash(&entries, &entry.path) {
-            Some(_) => {
-                found = true;
-                ()
-            }
-            None => (),
-        }
-        if !found {
-            changes.push(format!("D {}", entry.path));
+fn parse_option_time(time: &Option<String>) -> Result<Option<i64>, ZatsuError> {
+    let time = match time {
+        Some(time) => time,
+        None => return Ok(None),
+    };
+    match commons::parse_time(time) {
+        Ok(time) => Ok(Some(time)),
+        Err(error) => {
+            println!("Error: invalid time {}.", time);
+            Err(error)
         }
     }
 }
@@ -200,11 +229,12 @@ mod tests {
     use std::env;
     use std::fs;
 
+    use crate::CommitCommand;
     use crate::InitCommand;
 
     #[test]
     fn is_creatable() {
-        let _command = LogCommand::new();
+        let _command = LogCommand::new(None, None, None, &[], false, None, OutputFormat::Text);
     }
 
     #[test]
@@ -213,7 +243,7 @@ mod tests {
         env::set_current_dir("tmp").unwrap();
         let command = InitCommand::new(1);
         command.execute().unwrap();
-        let command = LogCommand::new();
+        let command = LogCommand::new(None, None, None, &[], false, None, OutputFormat::Text);
         let result = command.execute();
         assert!(result.is_ok());
         env::set_current_dir("..").unwrap();
@@ -223,10 +253,62 @@ mod tests {
         env::set_current_dir("tmp").unwrap();
         let command = InitCommand::new(2);
         command.execute().unwrap();
-        let command = LogCommand::new();
+        let command = LogCommand::new(None, None, None, &[], false, None, OutputFormat::Text);
         let result = command.execute();
         assert!(result.is_ok());
         env::set_current_dir("..").unwrap();
         fs::remove_dir_all("tmp").unwrap();
     }
+
+    #[test]
+    fn is_executable_with_filters() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::create_dir("dir").unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("dir/b.txt", "Hello, Zatsu!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+
+        let paths = vec!["dir".to_string()];
+        let command = LogCommand::new(
+            Some(1),
+            Some("2000-01-01"),
+            Some("2100-01-01"),
+            &paths,
+            true,
+            Some(50),
+            OutputFormat::Text,
+        );
+        let result = command.execute();
+        assert!(result.is_ok());
+        let repository = factory::load(".zatsu").unwrap();
+        let records = command.records(&repository).unwrap();
+        assert_eq!(1, records.len());
+        assert_eq!(2, records[0].number);
+        assert_eq!("dir/b.txt", records[0].changes[0].path);
+
+        let command = LogCommand::new(None, None, None, &[], false, None, OutputFormat::Json);
+        let result = command.execute();
+        assert!(result.is_ok());
+        let records = command.records(&repository).unwrap();
+        assert_eq!(2, records.len());
+        let command = LogCommand::new(
+            None,
+            Some("tomorrow"),
+            None,
+            &[],
+            false,
+            None,
+            OutputFormat::Text,
+        );
+        let result = command.execute();
+        assert!(result.is_err());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
 }
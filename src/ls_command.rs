@@ -0,0 +1,233 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::BTreeMap;
+
+use crate::commons;
+use crate::entry::EntryKind;
+use crate::error;
+use crate::repository::factory;
+use crate::Command;
+use crate::Entry;
+use crate::ZatsuError;
+
+pub struct LsCommand {
+    revision_number: i32,
+    path: String,
+    long: bool,
+    tree: bool,
+}
+
+#[derive(Default)]
+struct Node<'a> {
+    entry: Option<&'a Entry>,
+    children: BTreeMap<String, Node<'a>>,
+}
+
+impl Command for LsCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        for line in self.lines()? {
+            println!("{}", line);
+        }
+
+        Ok(())
+    }
+}
+
+impl LsCommand {
+    pub fn new(revision_number: i32, path: &str, long: bool, tree: bool) -> Self {
+        Self {
+            revision_number,
+            path: path.to_string(),
+            long,
+            tree,
+        }
+    }
+
+    fn lines(&self) -> Result<Vec<String>, ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+        let revision = commons::load_revision(&repository, self.revision_number)?;
+        let mut entries: Vec<&Entry> = revision
+            .entries
+            .iter()
+            .filter(|entry| commons::is_in_path(&entry.path, &self.path))
+            .collect();
+        if entries.is_empty() {
+            // An empty revision has nothing to list.
+            if commons::is_root_path(&self.path) {
+                return Ok(Vec::new());
+            }
+            println!(
+                "Error: {} not found in revision {}.",
+                self.path, self.revision_number
+            );
+            return Err(ZatsuError::new(error::CODE_FILE_NOT_FOUND));
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        if self.tree {
+            let mut root = Node::default();
+            for entry in &entries {
+                let mut node = &mut root;
+                for name in entry.path.split('/') {
+                    node = node.children.entry(name.to_string()).or_default();
+                }
+                node.entry = Some(entry);
+            }
+            let mut lines = vec![".".to_string()];
+            self.push_tree_lines(&root, "", &mut lines)?;
+            return Ok(lines);
+        }
+
+        let mut lines: Vec<String> = Vec::new();
+        for entry in entries {
+            let mut line = entry.path.clone();
+            if self.long {
+                line = format!("{} {}", self.details(entry)?, line);
+            }
+            lines.push(line);
+        }
+
+        Ok(lines)
+    }
+
+    fn push_tree_lines(
+        &self,
+        node: &Node,
+        indent: &str,
+        lines: &mut Vec<String>,
+    ) -> Result<(), ZatsuError> {
+        let count = node.children.len();
+        for (i, (name, child)) in node.children.iter().enumerate() {
+            let last = i == count - 1;
+            let branch = if last { "└── " } else { "├── " };
+            let mut line = format!("{}{}{}", indent, branch, name);
+            if let Some(entry) = child.entry {
+                if self.long {
+                    line = format!("{}{}[{}] {}", indent, branch, self.details(entry)?, name);
+                }
+            }
+            lines.push(line);
+
+            let child_indent = if last { "    " } else { "│   " };
+            self.push_tree_lines(child, &format!("{}{}", indent, child_indent), lines)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns permission, hash and decompressed size of an entry.
+    fn details(&self, entry: &Entry) -> Result<String, ZatsuError> {
+        let size = commons::object_size(&entry.hash)?;
+        let mark = match entry.kind {
+            EntryKind::File => "-",
+            EntryKind::SymbolicLink => "l",
+        };
+
+        Ok(format!(
+            "{}{:03o} {} {:>10}",
+            mark, entry.permission, entry.hash, size
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = LsCommand::new(1, ".", false, false);
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::create_dir_all("dir/sub").unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        fs::write("dir/b.txt", "").unwrap();
+        fs::write("dir/sub/c.txt", "").unwrap();
+        fs::write("e.txt", "").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+
+        let command = LsCommand::new(1, ".", false, false);
+        let result = command.execute();
+        assert!(result.is_ok());
+        assert_eq!(
+            vec!["a.txt", "dir/b.txt", "dir/sub/c.txt", "e.txt"],
+            command.lines().unwrap()
+        );
+        let command = LsCommand::new(1, "dir/sub", false, false);
+        assert_eq!(vec!["dir/sub/c.txt"], command.lines().unwrap());
+        let command = LsCommand::new(1, "a.txt", true, false);
+        let lines = command.lines().unwrap();
+        assert!(lines[0].starts_with("-6"));
+        assert!(lines[0].ends_with("         13 a.txt"));
+        let command = LsCommand::new(1, ".", false, true);
+        assert_eq!(
+            vec![
+                ".",
+                "├── a.txt",
+                "├── dir",
+                "│   ├── b.txt",
+                "│   └── sub",
+                "│       └── c.txt",
+                "└── e.txt",
+            ],
+            command.lines().unwrap()
+        );
+        let command = LsCommand::new(1, "f.txt", false, false);
+        let result = command.execute();
+        assert!(result.is_err());
+
+        fs::remove_file("a.txt").unwrap();
+        fs::remove_file("e.txt").unwrap();
+        fs::remove_dir_all("dir").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        let command = LsCommand::new(2, ".", false, true);
+        let result = command.execute();
+        assert!(result.is_ok());
+        assert!(command.lines().unwrap().is_empty());
+        let command = LsCommand::new(2, "dir", false, false);
+        let result = command.execute();
+        assert!(result.is_err());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+}
@@ -20,38 +20,73 @@
  * DEALINGS IN THE SOFTWARE.
  */
 
+mod blame_command;
+mod cat_command;
+mod change;
 mod command;
 mod commit_command;
 mod commons;
+mod diff_command;
 mod entry;
 mod error;
+mod export_command;
 mod file_path_producer;
 mod forget_command;
 mod get_command;
+mod grep_command;
+mod history_command;
+mod import_command;
+mod index;
 mod init_command;
 mod log_command;
+mod ls_command;
 mod repository;
+mod restore_command;
 mod revision;
+mod status_command;
 mod upgrade_command;
 
+use clap::error::ErrorKind;
+use clap::ArgGroup;
+use clap::CommandFactory;
 use clap::Parser;
 use clap::Subcommand;
 
+use crate::blame_command::BlameCommand;
+use crate::cat_command::CatCommand;
 use crate::command::Command;
+use crate::command::OutputFormat;
 use crate::commit_command::CommitCommand;
+use crate::diff_command::DiffCommand;
 use crate::entry::Entry;
 use crate::error::ZatsuError;
+use crate::export_command::ExportCommand;
+use crate::export_command::ExportFormat;
 use crate::file_path_producer::FilePathProducer;
 use crate::forget_command::ForgetCommand;
+use crate::forget_command::KeepPolicy;
+use crate::get_command::ConflictPolicy;
 use crate::get_command::GetCommand;
+use crate::grep_command::GrepCommand;
+use crate::history_command::HistoryCommand;
+use crate::import_command::ImportCommand;
 use crate::init_command::InitCommand;
 use crate::log_command::LogCommand;
+use crate::ls_command::LsCommand;
 use crate::repository::Repository;
+use crate::restore_command::RestoreCommand;
 use crate::revision::Revision;
+use crate::status_command::StatusCommand;
 use crate::upgrade_command::UpgradeCommand;
 
 #[derive(Parser)]
 struct Arguments {
+    /// Output format of commands that support machine-readable outputs
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// Same as --format json
+    #[arg(long, global = true)]
+    json: bool,
     /// Command you want to do
     #[command(subcommand)]
     command: Option<CommandKind>,
@@ -64,18 +99,203 @@ struct InitArguments {
     version: Option<i32>,
 }
 
+#[derive(Parser, PartialEq)]
+struct CommitArguments {
+    /// Description of the revision to be commited
+    #[arg(short, long)]
+    message: Option<String>,
+    /// Edit the description with $EDITOR if no message is specified
+    #[arg(short, long)]
+    edit: bool,
+    /// Commit even if nothing is changed from the latest revision
+    #[arg(long)]
+    allow_empty: bool,
+}
+
+#[derive(Parser, PartialEq)]
+struct DiffArguments {
+    /// Revisions to compare. Current files are compared if second one is omitted
+    #[arg(num_args = 0..=2)]
+    revisions: Vec<i32>,
+    /// Also detect renamed files whose contents are similar at least this percent
+    #[arg(short = 'M', long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(0..=100))]
+    similarity: Option<u8>,
+    /// Paths to limit the comparison
+    #[arg(last = true)]
+    paths: Vec<String>,
+}
+
 #[derive(Parser, PartialEq)]
 struct GetArguments {
     /// Revision to get a file or directory
     revision: i32,
     /// Path to get a file or directory
     path: String,
+    /// Path to write the file or directory to
+    #[arg(short, long, conflicts_with = "into")]
+    output: Option<String>,
+    /// Directory to write the file or directory into
+    #[arg(short, long)]
+    into: Option<String>,
+    /// Keep the original name instead of adding the revision number
+    #[arg(short, long)]
+    exact: bool,
+    /// How to handle files that already exist
+    #[arg(short, long, value_enum, default_value_t = ConflictPolicy::Overwrite)]
+    conflict: ConflictPolicy,
+}
+
+#[derive(Parser, PartialEq)]
+struct CatArguments {
+    /// Revision to get a file
+    revision: i32,
+    /// Path of a file to be written to standard output
+    path: String,
+}
+
+#[derive(Parser, PartialEq)]
+struct LsArguments {
+    /// Revision to list
+    revision: i32,
+    /// Path of a file or directory to list. All files are listed if omitted
+    #[arg(default_value = ".")]
+    path: String,
+    /// Show permissions, hashes and sizes of files
+    #[arg(short, long)]
+    long: bool,
+    /// Show files as a tree
+    #[arg(long)]
+    tree: bool,
+}
+
+#[derive(Parser, PartialEq)]
+struct ExportArguments {
+    /// Revision to export
+    revision: i32,
+    /// Path of a file or directory to export. All files are exported if omitted
+    #[arg(default_value = ".")]
+    path: String,
+    /// Archive format. Guessed from the output file name if omitted
+    #[arg(short, long, value_enum)]
+    format: Option<ExportFormat>,
+    /// Archive file to write. "-" means standard output
+    #[arg(short, long)]
+    output: String,
+}
+
+#[derive(Parser, PartialEq)]
+struct RestoreArguments {
+    /// Revision to restore files from
+    revision: i32,
+    /// Paths to restore. All files are restored if omitted
+    paths: Vec<String>,
+    /// Remove files that do not exist in the revision
+    #[arg(short, long)]
+    delete: bool,
+    /// Overwrite files even if they have uncommitted changes
+    #[arg(short, long)]
+    force: bool,
+}
+
+#[derive(Parser, PartialEq)]
+struct LogArguments {
+    /// Maximum number of revisions to show
+    #[arg(short = 'n', long)]
+    limit: Option<usize>,
+    /// Show only revisions commited at or after this time
+    #[arg(long)]
+    since: Option<String>,
+    /// Show only revisions commited at or before this time
+    #[arg(long)]
+    until: Option<String>,
+    /// Show only counts of added, modified, deleted and renamed files
+    #[arg(long)]
+    stat: bool,
+    /// Also detect renamed files whose contents are similar at least this percent
+    #[arg(short = 'M', long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(0..=100))]
+    similarity: Option<u8>,
+    /// Show only revisions that change these files or directories
+    #[arg(last = true)]
+    paths: Vec<String>,
+}
+
+#[derive(Parser, PartialEq)]
+struct HistoryArguments {
+    /// Path of a file to show revisions that changed it
+    path: String,
+    /// Follow the file across renames
+    #[arg(long)]
+    follow: bool,
+}
+
+#[derive(Parser, PartialEq)]
+struct BlameArguments {
+    /// Path of a file to show the revision of each line
+    path: String,
+    /// Revision of the file. The latest revision is used if omitted
+    revision: Option<i32>,
+}
+
+#[derive(Parser, PartialEq)]
+struct GrepArguments {
+    /// Regular expression to search for
+    pattern: String,
+    /// Revisions to search, like "N", "N..M", "N.." or "..M". All revisions are searched if omitted
+    #[arg(short, long)]
+    rev: Option<String>,
+    /// Paths to limit the search
+    #[arg(last = true)]
+    paths: Vec<String>,
+}
+
+#[derive(Parser, PartialEq)]
+struct ImportArguments {
+    /// Tar archive, optionally gzipped, or directory to import
+    path: String,
+    /// Time of the new revision. RFC 3339, "YYYY-MM-DD[ HH:MM[:SS]]" or seconds since the epoch
+    #[arg(short, long)]
+    time: String,
+    /// Description of the new revision
+    #[arg(short, long)]
+    message: Option<String>,
 }
 
 #[derive(Parser, PartialEq)]
+#[command(group(ArgGroup::new("policy").required(true).multiple(true)))]
 struct ForgetArguments {
-    /// Revision count to keep
-    count: i32,
+    /// Revision count to keep. Same as --keep-last
+    #[arg(group = "policy")]
+    count: Option<usize>,
+    /// Keep the last N revisions by revision number
+    #[arg(long, value_name = "N", group = "policy")]
+    keep_last: Option<usize>,
+    /// Keep the newest revision of each of the newest N hours by commited time
+    #[arg(long, value_name = "N", group = "policy")]
+    keep_hourly: Option<usize>,
+    /// Keep the newest revision of each of the newest N days by commited time
+    #[arg(long, value_name = "N", group = "policy")]
+    keep_daily: Option<usize>,
+    /// Keep the newest revision of each of the newest N weeks by commited time
+    #[arg(long, value_name = "N", group = "policy")]
+    keep_weekly: Option<usize>,
+    /// Keep the newest revision of each of the newest N months by commited time
+    #[arg(long, value_name = "N", group = "policy")]
+    keep_monthly: Option<usize>,
+    /// Keep the newest revision of each of the newest N years by commited time
+    #[arg(long, value_name = "N", group = "policy")]
+    keep_yearly: Option<usize>,
+    /// Forget the oldest revisions until the repository fits in this size, like "5GiB"
+    #[arg(long, value_name = "SIZE", group = "policy")]
+    max_size: Option<String>,
+    /// Forget this revision. Can be specified multiple times
+    #[arg(long, value_name = "N", group = "policy")]
+    revision: Vec<i32>,
+    /// Forget revisions in this range, like "10..20". Both ends are included
+    #[arg(long, value_name = "RANGE", group = "policy")]
+    range: Vec<String>,
+    /// Forget revisions older than this duration, like "90d". Units are h, d, w, m and y
+    #[arg(long, value_name = "DURATION", group = "policy")]
+    older_than: Option<String>,
 }
 
 #[derive(Subcommand, PartialEq)]
@@ -83,11 +303,31 @@ enum CommandKind {
     /// Initialize a repository into this directory
     Init(InitArguments),
     /// Commit current files into this directory's repository
-    Commit,
+    Commit(CommitArguments),
     /// Show logs of this directory's repository
-    Log,
+    Log(LogArguments),
+    /// Show changes of current files from the latest revision
+    Status,
+    /// Show revisions that changed a file
+    History(HistoryArguments),
+    /// Show the revision that introduced each line of a file
+    Blame(BlameArguments),
+    /// Search contents of files in revisions with a regular expression
+    Grep(GrepArguments),
+    /// Show differences between revisions or current files
+    Diff(DiffArguments),
     /// Get a file or directory that is specified
     Get(GetArguments),
+    /// Restore current files to a revision
+    Restore(RestoreArguments),
+    /// Write content of a file that is specified to standard output
+    Cat(CatArguments),
+    /// List files in a revision
+    Ls(LsArguments),
+    /// Export a revision as an archive
+    Export(ExportArguments),
+    /// Import a tar archive or a directory as a new revision without touching working files
+    Import(ImportArguments),
     /// Remove stored revisions to shrink this directory's repository to specified size
     Forget(ForgetArguments),
     /// Upgrade this repository
@@ -96,31 +336,165 @@ enum CommandKind {
 
 fn main() -> Result<(), ZatsuError> {
     let arguments = Arguments::parse();
-    let mut command = CommandKind::Commit;
+    let mut format = arguments.format;
+    if arguments.json {
+        format = OutputFormat::Json;
+    }
+    let mut command = CommandKind::Commit(CommitArguments {
+        message: None,
+        edit: false,
+        allow_empty: false,
+    });
     if arguments.command.is_some() {
         command = arguments.command.unwrap();
     }
+    if format != OutputFormat::Text
+        && !matches!(command, CommandKind::Log(_) | CommandKind::History(_))
+    {
+        Arguments::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--format json and --json are supported only by log and history",
+            )
+            .exit();
+    }
 
-    if command == CommandKind::Commit {
-        let command = CommitCommand::new();
+    if let CommandKind::Commit(arguments) = command {
+        let message = arguments.message.unwrap_or_default();
+        let command = CommitCommand::new(&message, arguments.edit, arguments.allow_empty);
         match command.execute() {
             Ok(()) => (),
             Err(error) => return Err(error),
         };
-    } else if command == CommandKind::Log {
-        let command = LogCommand::new();
+    } else if let CommandKind::Log(arguments) = command {
+        let command = LogCommand::new(
+            arguments.limit,
+            arguments.since.as_deref(),
+            arguments.until.as_deref(),
+            &arguments.paths,
+            arguments.stat,
+            arguments.similarity,
+            format,
+        );
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::History(arguments) = command {
+        let command = HistoryCommand::new(&arguments.path, arguments.follow, format);
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::Blame(arguments) = command {
+        let command = BlameCommand::new(&arguments.path, arguments.revision);
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::Grep(arguments) = command {
+        let command = GrepCommand::new(
+            &arguments.pattern,
+            arguments.rev.as_deref(),
+            &arguments.paths,
+        );
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if command == CommandKind::Status {
+        let command = StatusCommand::new();
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::Diff(arguments) = command {
+        let command =
+            DiffCommand::new(&arguments.revisions, &arguments.paths, arguments.similarity);
         match command.execute() {
             Ok(()) => (),
             Err(error) => return Err(error),
         };
     } else if let CommandKind::Get(arguments) = command {
-        let command = GetCommand::new(arguments.revision, &arguments.path);
+        let command = GetCommand::new(
+            arguments.revision,
+            &arguments.path,
+            arguments.output.as_deref(),
+            arguments.into.as_deref(),
+            arguments.exact,
+            arguments.conflict,
+        );
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::Restore(arguments) = command {
+        let command = RestoreCommand::new(
+            arguments.revision,
+            &arguments.paths,
+            arguments.delete,
+            arguments.force,
+        );
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::Cat(arguments) = command {
+        let command = CatCommand::new(arguments.revision, &arguments.path);
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::Ls(arguments) = command {
+        let command = LsCommand::new(
+            arguments.revision,
+            &arguments.path,
+            arguments.long,
+            arguments.tree,
+        );
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::Export(arguments) = command {
+        let command = ExportCommand::new(
+            arguments.revision,
+            &arguments.path,
+            arguments.format,
+            &arguments.output,
+        );
+        match command.execute() {
+            Ok(()) => (),
+            Err(error) => return Err(error),
+        };
+    } else if let CommandKind::Import(arguments) = command {
+        let message = arguments.message.unwrap_or_default();
+        let command = ImportCommand::new(&arguments.path, &arguments.time, &message);
         match command.execute() {
             Ok(()) => (),
             Err(error) => return Err(error),
         };
     } else if let CommandKind::Forget(arguments) = command {
-        let command = ForgetCommand::new(arguments.count);
+        // The revision count is an alias of --keep-last, so the larger one wins.
+        let keep_last = match (arguments.count, arguments.keep_last) {
+            (Some(count), Some(keep_last)) => Some(count.max(keep_last)),
+            (count, keep_last) => count.or(keep_last),
+        };
+        let policy = KeepPolicy {
+            last: keep_last,
+            hourly: arguments.keep_hourly,
+            daily: arguments.keep_daily,
+            weekly: arguments.keep_weekly,
+            monthly: arguments.keep_monthly,
+            yearly: arguments.keep_yearly,
+        };
+        let command = ForgetCommand::new(
+            policy,
+            arguments.max_size.as_deref(),
+            &arguments.revision,
+            &arguments.range,
+            arguments.older_than.as_deref(),
+        );
         match command.execute() {
             Ok(()) => (),
             Err(error) => return Err(error),
@@ -35,12 +35,15 @@ pub trait Repository {
     fn set_revision_numbers(&mut self, revision_numbers: &Vec<i32>);
     fn version(&self) -> i32;
     fn latest_revision(&self) -> i32;
+    fn next_revision(&self) -> i32;
     fn to_serializable_v1(&self) -> SerializableRepositoryV1;
     fn object_hash(&self, values: &Vec<u8>) -> String;
 }
 
 struct RepositoryBase {
     revision_numbers: Vec<i32>,
+    // Numbers of forgotten revisions are not reused.
+    next_revision_number: i32,
     version: i32,
 }
 
@@ -57,6 +60,7 @@ impl Repository for RepositoryBase {
     }
 
     fn set_revision_numbers(&mut self, revision_numbers: &Vec<i32>) {
+        self.next_revision_number = self.next_revision();
         self.revision_numbers = revision_numbers.clone();
     }
 
@@ -73,9 +77,14 @@ impl Repository for RepositoryBase {
         return self.revision_numbers[count - 1];
     }
 
+    fn next_revision(&self) -> i32 {
+        self.next_revision_number.max(self.latest_revision() + 1)
+    }
+
     fn to_serializable_v1(&self) -> SerializableRepositoryV1 {
         SerializableRepositoryV1 {
             revision_numbers: self.revision_numbers.clone(),
+            next_revision_number: self.next_revision(),
         }
     }
 
@@ -88,6 +97,7 @@ impl RepositoryBase {
     fn from_serializable_v1(repository_v1: &SerializableRepositoryV1) -> Self {
         RepositoryBase {
             revision_numbers: repository_v1.revision_numbers.clone(),
+            next_revision_number: repository_v1.next_revision_number,
             version: 1,
         }
     }
@@ -118,6 +128,10 @@ impl Repository for RepositoryV1 {
         self.base.latest_revision()
     }
 
+    fn next_revision(&self) -> i32 {
+        self.base.next_revision()
+    }
+
     fn to_serializable_v1(&self) -> SerializableRepositoryV1 {
         self.base.to_serializable_v1()
     }
@@ -152,6 +166,10 @@ impl Repository for RepositoryV2 {
         self.base.latest_revision()
     }
 
+    fn next_revision(&self) -> i32 {
+        self.base.next_revision()
+    }
+
     fn to_serializable_v1(&self) -> SerializableRepositoryV1 {
         self.base.to_serializable_v1()
     }
@@ -167,6 +185,7 @@ pub mod factory {
     pub fn new(version: i32) -> Box<dyn Repository> {
         let base = RepositoryBase {
             revision_numbers: Vec::new(),
+            next_revision_number: 1,
             version: version,
         };
         if version == 1 {
@@ -211,6 +230,7 @@ pub mod factory {
     pub fn with_arguments(revision_numbers: &Vec<i32>, version: i32) -> Box<dyn Repository> {
         let base = RepositoryBase {
             revision_numbers: revision_numbers.to_vec(),
+            next_revision_number: 1,
             version: version,
         };
 
@@ -229,6 +249,9 @@ pub mod factory {
 #[derive(Serialize, Deserialize)]
 pub struct SerializableRepositoryV1 {
     revision_numbers: Vec<i32>,
+    // Missing in repositories saved by older versions.
+    #[serde(default)]
+    next_revision_number: i32,
 }
 
 impl SerializableRepositoryV1 {
@@ -312,6 +335,25 @@ mod tests {
         assert_eq!(3, repository.latest_revision());
     }
 
+    #[test]
+    fn repository_is_gettable_next_revision() {
+        let mut repository = factory::with_arguments(
+            &vec![1, 2, 3],
+            2,
+        );
+        assert_eq!(4, repository.next_revision());
+        repository.set_revision_numbers(&vec![1]);
+        assert_eq!(4, repository.next_revision());
+        repository.set_revision_numbers(&vec![]);
+        assert_eq!(4, repository.next_revision());
+
+        let repository_v1: SerializableRepositoryV1 = serde_json::from_str(
+            "{\"revision_numbers\":[1,2]}",
+        ).unwrap();
+        let repository = RepositoryBase::from_serializable_v1(&repository_v1);
+        assert_eq!(3, repository.next_revision());
+    }
+
     #[test]
     fn repository_is_convertable_to_repository_v1() {
         let repository = factory::with_arguments(
@@ -0,0 +1,259 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::change;
+use crate::commons;
+use crate::error;
+use crate::repository::factory;
+use crate::status_command;
+use crate::Command;
+use crate::Entry;
+use crate::ZatsuError;
+
+pub struct RestoreCommand {
+    revision_number: i32,
+    paths: Vec<String>,
+    delete: bool,
+    force: bool,
+}
+
+impl Command for RestoreCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+        let revision = commons::load_revision(&repository, self.revision_number)?;
+        let mut latest_entries: Vec<Entry> = Vec::new();
+        let latest_revision = repository.latest_revision();
+        if latest_revision > 0 {
+            latest_entries = commons::load_revision(&repository, latest_revision)?.entries;
+        }
+        let working_entries = status_command::working_entries(&repository)?;
+
+        // Files that differ from the latest revision have uncommitted changes.
+        let mut modified_paths: HashSet<String> = HashSet::new();
+        for change in change::compare_entries(&working_entries, &latest_entries) {
+            if change.new_entry.is_some() {
+                modified_paths.insert(change.path);
+            }
+        }
+
+        let mut working_map: HashMap<&str, &Entry> = HashMap::new();
+        for entry in &working_entries {
+            working_map.insert(&entry.path, entry);
+        }
+        let mut restored_entries: Vec<&Entry> = Vec::new();
+        for entry in &revision.entries {
+            if !self.is_specified(&entry.path) {
+                continue;
+            }
+            if let Some(working_entry) = working_map.get(entry.path.as_str()) {
+                if working_entry.hash == entry.hash
+                    && working_entry.permission == entry.permission
+                    && working_entry.kind == entry.kind
+                {
+                    continue;
+                }
+            }
+            restored_entries.push(entry);
+        }
+
+        let mut removed_paths: Vec<&str> = Vec::new();
+        if self.delete {
+            let revision_paths: HashSet<&str> = revision
+                .entries
+                .iter()
+                .map(|entry| entry.path.as_str())
+                .collect();
+            for entry in &latest_entries {
+                if self.is_specified(&entry.path)
+                    && !revision_paths.contains(entry.path.as_str())
+                    && working_map.contains_key(entry.path.as_str())
+                {
+                    removed_paths.push(&entry.path);
+                }
+            }
+        }
+
+        let mut conflicted_paths: Vec<&str> = Vec::new();
+        for entry in &restored_entries {
+            if modified_paths.contains(&entry.path) {
+                conflicted_paths.push(&entry.path);
+            }
+        }
+        for path in &removed_paths {
+            if modified_paths.contains(*path) {
+                conflicted_paths.push(path);
+            }
+        }
+        if !conflicted_paths.is_empty() && !self.force {
+            println!(
+                "Error: these files have uncommitted changes. To overwrite them, use --force."
+            );
+            for path in conflicted_paths {
+                println!("{}", path);
+            }
+            return Err(ZatsuError::new(error::CODE_UNCOMMITTED_CHANGES_FOUND));
+        }
+
+        for entry in &restored_entries {
+            println!("Restoring: {}", entry.path);
+            if let Some(parent) = Path::new(&entry.path).parent() {
+                match fs::create_dir_all(parent) {
+                    Ok(()) => (),
+                    Err(_) => return Err(ZatsuError::new(error::CODE_CREATING_DIRECTORY_FAILED)),
+                };
+            }
+            commons::write_entry(entry, &entry.path)?;
+        }
+        for path in &removed_paths {
+            println!("Removing: {}", path);
+            match fs::remove_file(path) {
+                Ok(()) => (),
+                Err(_) => return Err(ZatsuError::new(error::CODE_REMOVING_FILE_FAILED)),
+            };
+            remove_empty_directories(path);
+        }
+
+        println!();
+        println!(
+            "{} file(s) restored and {} file(s) removed from revision {}.",
+            restored_entries.len(),
+            removed_paths.len(),
+            self.revision_number
+        );
+
+        Ok(())
+    }
+}
+
+impl RestoreCommand {
+    pub fn new(revision_number: i32, paths: &[String], delete: bool, force: bool) -> Self {
+        Self {
+            revision_number,
+            paths: paths.to_vec(),
+            delete,
+            force,
+        }
+    }
+
+    fn is_specified(&self, path: &str) -> bool {
+        self.paths.is_empty()
+            || self
+                .paths
+                .iter()
+                .any(|specified_path| commons::is_in_path(path, specified_path))
+    }
+}
+
+fn remove_empty_directories(path: &str) {
+    let mut option = Path::new(path).parent();
+    while let Some(directory) = option {
+        if directory.as_os_str().is_empty() || fs::remove_dir(directory).is_err() {
+            break;
+        }
+        option = directory.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = RestoreCommand::new(1, &[], false, false);
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, Zatsu!").unwrap();
+        fs::create_dir("dir").unwrap();
+        fs::write("dir/b.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+
+        let command = RestoreCommand::new(1, &[], false, false);
+        let result = command.execute();
+        assert!(result.is_ok());
+        assert_eq!("Hello, World!", fs::read_to_string("a.txt").unwrap());
+        assert!(Path::new("dir/b.txt").exists());
+
+        let command = RestoreCommand::new(1, &[], true, false);
+        let result = command.execute();
+        assert!(result.is_ok());
+        assert!(!Path::new("dir").exists());
+
+        let command = RestoreCommand::new(2, &["dir".to_string()], false, false);
+        let result = command.execute();
+        assert!(result.is_ok());
+        assert_eq!("Hello, World!", fs::read_to_string("dir/b.txt").unwrap());
+        assert_eq!("Hello, World!", fs::read_to_string("a.txt").unwrap());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn is_not_executable_with_uncommitted_changes() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, Zatsu!").unwrap();
+
+        let command = RestoreCommand::new(1, &[], false, false);
+        let result = command.execute();
+        assert!(result.is_err());
+        assert_eq!("Hello, Zatsu!", fs::read_to_string("a.txt").unwrap());
+
+        let command = RestoreCommand::new(1, &[], false, true);
+        let result = command.execute();
+        assert!(result.is_ok());
+        assert_eq!("Hello, World!", fs::read_to_string("a.txt").unwrap());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+}
@@ -33,7 +33,6 @@ use crate::error::ZatsuError;
 pub struct Revision {
     pub commited: i64,
     pub entries: Vec<Entry>,
-    // TOOD: Use this reserved field.
     pub description: String,
 }
 
@@ -82,7 +81,7 @@ mod tests {
         env::set_current_dir("tmp").unwrap();
         let command = InitCommand::new(1);
         command.execute().unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
         command.execute().unwrap();
         let result = Revision::load(".zatsu/revisions/01/1.json");
         assert!(result.is_ok());
@@ -93,7 +92,7 @@ mod tests {
         env::set_current_dir("tmp").unwrap();
         let command = InitCommand::new(2);
         command.execute().unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
         command.execute().unwrap();
         let result = Revision::load(".zatsu/revisions/01/1.json");
         assert!(result.is_ok());
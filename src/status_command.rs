@@ -0,0 +1,167 @@
+/*
+ * Copyright (c) 2024 Yasuaki Gohko
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a
+ * copy of this software and associated documentation files (the "Software"),
+ * to deal in the Software without restriction, including without limitation
+ * the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ * and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ * THE ABOVE LISTED COPYRIGHT HOLDER(S) BE LIABLE FOR ANY CLAIM, DAMAGES OR
+ * OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ * ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ * DEALINGS IN THE SOFTWARE.
+ */
+
+use std::path::Path;
+
+use crate::change;
+use crate::commit_command;
+use crate::commons;
+use crate::error;
+use crate::index::Index;
+use crate::repository::factory;
+use crate::Command;
+use crate::Entry;
+use crate::FilePathProducer;
+use crate::Repository;
+use crate::Revision;
+use crate::ZatsuError;
+
+pub struct StatusCommand {}
+
+impl Command for StatusCommand {
+    fn execute(&self) -> Result<(), ZatsuError> {
+        let repository = match factory::load(".zatsu") {
+            Ok(repository) => repository,
+            Err(_) => {
+                println!("Error: repository not found. To create repository, execute zatsu init.");
+                return Err(ZatsuError::new(error::CODE_LOADING_REPOSITORY_FAILED));
+            }
+        };
+
+        let entries = working_entries(&repository)?;
+        let mut latest_entries: Vec<Entry> = Vec::new();
+        let latest_revision = repository.latest_revision();
+        if latest_revision > 0 {
+            let revision = Revision::load(commons::revision_path(latest_revision))?;
+            latest_entries = revision.entries;
+        }
+
+        let changes = change::compare_entries(&entries, &latest_entries);
+        if changes.is_empty() {
+            println!("No changes from revision {}.", latest_revision);
+            return Ok(());
+        }
+        for change in changes {
+            println!("{}", change);
+        }
+
+        Ok(())
+    }
+}
+
+impl StatusCommand {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Returns entries of the current files without saving any objects.
+pub fn working_entries(repository: &Box<dyn Repository>) -> Result<Vec<Entry>, ZatsuError> {
+    let index_path = Path::new(".zatsu/index.json");
+    let previous_index = Index::load_or_new(index_path, repository.version());
+    let mut index = Index::new(repository.version());
+
+    let mut producer = FilePathProducer::new(".".to_string());
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut done = false;
+    while !done {
+        match producer.next() {
+            Ok(path) => {
+                let entry = commit_command::process_file(
+                    &path,
+                    repository,
+                    &previous_index,
+                    &mut index,
+                    false,
+                )?;
+                entries.push(entry);
+            }
+            Err(error) => {
+                if error.code == error::CODE_PRODUCING_FINISHED {
+                    done = true;
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    use crate::CommitCommand;
+    use crate::InitCommand;
+
+    #[test]
+    fn is_creatable() {
+        let _command = StatusCommand::new();
+    }
+
+    #[test]
+    fn is_executable() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(1);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = StatusCommand::new();
+        let result = command.execute();
+        assert!(result.is_ok());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let command = CommitCommand::new("", false, false);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, Zatsu!").unwrap();
+        let command = StatusCommand::new();
+        let result = command.execute();
+        assert!(result.is_ok());
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+
+    #[test]
+    fn working_entries_do_not_save_objects() {
+        fs::create_dir("tmp").unwrap();
+        env::set_current_dir("tmp").unwrap();
+        let command = InitCommand::new(2);
+        command.execute().unwrap();
+        fs::write("a.txt", "Hello, World!").unwrap();
+        let repository = factory::load(".zatsu").unwrap();
+        let entries = working_entries(&repository).unwrap();
+        assert_eq!(1, entries.len());
+        assert_eq!("a.txt", entries[0].path);
+        assert!(!commons::object_exists(&entries[0].hash));
+        env::set_current_dir("..").unwrap();
+        fs::remove_dir_all("tmp").unwrap();
+    }
+}
@@ -170,6 +170,7 @@ fn update_entries(revision_numbers: &Vec<i32>) -> Result<(), ZatsuError> {
                 path: entry.path,
                 hash: new_hash,
                 permission: entry.permission,
+                kind: entry.kind,
             };
             new_entries.push(new_entry);
         }
@@ -203,7 +204,7 @@ mod test {
         let command = InitCommand::new(1);
         command.execute().unwrap();
         fs::write("a.txt", "Hello, World!").unwrap();
-        let command = CommitCommand::new();
+        let command = CommitCommand::new("", false, false);
         command.execute().unwrap();
         let command = UpgradeCommand::new();
         let result = command.execute();